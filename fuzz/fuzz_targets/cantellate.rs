@@ -0,0 +1,55 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use cantellation::{
+    cantellate::{cantellate, CantellateParams, Placement},
+    mesh::Mesh,
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use libfuzzer_sys::fuzz_target;
+
+/// Arbitrary mesh with invalid indices, degenerate faces and any floating point values.
+#[derive(Debug, Arbitrary)]
+struct Input {
+    vertices: Vec<[f64; 3]>,
+    // small indices are mostly valid, the rest are out of range
+    faces: Vec<Vec<u8>>,
+    factor: f64,
+    epsilon: f64,
+    placement: u8,
+    inset: f64,
+    segments: u8,
+    min_angle: f64,
+}
+
+fuzz_target!(|input: Input| {
+    let mesh = Mesh {
+        vertices: input
+            .vertices
+            .iter()
+            .map(|&[x, y, z]| Vec3::new(x, y, z))
+            .collect(),
+        faces: input
+            .faces
+            .iter()
+            .map(|face| face.iter().map(|&index| index as usize).collect())
+            .collect(),
+        ..Default::default()
+    };
+    let params = CantellateParams {
+        factor: input.factor,
+        tolerances: Tolerances::new(input.epsilon),
+        placement: match input.placement % 4 {
+            0 => Placement::Normal,
+            1 => Placement::Centroid,
+            2 => Placement::Inset,
+            _ => Placement::InsetNormal,
+        },
+        inset: input.inset,
+        // limit the size of rounded results
+        segments: (input.segments % 8) as usize,
+        min_angle: input.min_angle,
+    };
+    let _ = cantellate(&mesh, &params);
+});
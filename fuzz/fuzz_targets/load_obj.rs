@@ -0,0 +1,11 @@
+#![no_main]
+
+use cantellation::mesh::Mesh;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // files which are not UTF-8 are rejected before parsing
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = Mesh::<f64>::parse_obj(text);
+    }
+});
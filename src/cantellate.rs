@@ -1,21 +1,159 @@
 use crate::{
     mesh::{Mesh, SmallVec},
+    normals::{FaceNormals, NormalsReport},
+    tolerance::Tolerances,
     vec3::Vec3,
 };
 use ahash::AHashMap;
+use clap::ValueEnum;
 use num_traits::{float::Float, FromPrimitive, ToPrimitive};
 
+/// Parameters of the cantellation.
+#[derive(Debug, Clone, Copy)]
+pub struct CantellateParams<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// The cantellation factor is an absolute distance from the original face to the cantellated face.
+    pub factor: N,
+
+    /// Tolerances to compare floating point numbers.
+    pub tolerances: Tolerances<N>,
+
+    /// How the cantellated vertices are placed relative to the original vertex.
+    pub placement: Placement,
+
+    /// In-plane inset distance for the `Placement::InsetNormal`.
+    pub inset: N,
+
+    /// Count of faces in each new edge strip.
+    /// If it's greater than 1, edge strips are rounded along the circular arc between face normals
+    /// and vertex caps become spherical patches.
+    /// Only `Placement::Normal` is rounded, see `check`, other placements keep flat strips.
+    pub segments: usize,
+
+    /// Minimal dihedral angle in radians between normals of neighbour faces to cantellate their common edge.
+    /// Smoother edges stay connected: there is no edge strip and faces share the cantellated vertex.
+    /// Zero cantellates all edges.
+    pub min_angle: N,
+}
+
+impl<N> CantellateParams<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Push faces along their normals by the factor, all edges are cantellated by flat strips.
+    pub fn new(factor: N, tolerances: Tolerances<N>) -> Self {
+        Self {
+            factor,
+            tolerances,
+            placement: Placement::Normal,
+            inset: factor,
+            segments: 1,
+            min_angle: N::zero(),
+        }
+    }
+
+    /// Check that the parameters can be combined.
+    /// Rounding needs the center of the sphere through all corners of the vertex.
+    /// It's the original vertex for offsets along face normals, corners of other placements have no common center.
+    pub fn check(&self) -> Result<(), String> {
+        if self.segments > 1 && self.placement != Placement::Normal {
+            let placement = self
+                .placement
+                .to_possible_value()
+                .map(|value| value.get_name().to_owned())
+                .unwrap_or_default();
+            return Err(format!(
+                "Segments greater than 1 require the normal placement, corners of the {placement} placement can't be rounded"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Strategy to place the cantellated vertex of the face corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Placement {
+    /// Push the vertex along the face normal by the factor. Faces are pushed outwards.
+    #[default]
+    Normal,
+
+    /// Move the vertex toward the face centroid by the factor. Faces are shrunk.
+    Centroid,
+
+    /// Move the vertex inside the face plane so that face edges are inset by the factor.
+    Inset,
+
+    /// Inset face edges by the inset distance and push the vertex along the face normal by the factor.
+    InsetNormal,
+}
+
+/// Result of the selective cantellation.
+#[derive(Debug, Clone)]
+pub struct Cantellated<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    pub mesh: Mesh<N>,
+
+    /// Faces of the result which are expanded from selected faces only:
+    /// pushed selected faces, edge strips between them and caps of vertices with only selected faces.
+    /// It is the selection of the next iteration.
+    pub selected: Vec<bool>,
+
+    /// Kind of each face of the result.
+    pub kinds: Vec<FaceKind>,
+
+    /// Faces of the source mesh with fallback normals.
+    pub normals: NormalsReport,
+}
+
+/// Class of the face in the cantellated mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaceKind {
+    /// Pushed face of the original mesh.
+    Face,
+
+    /// Strip between two faces produced by the original edge.
+    Edge,
+
+    /// Cap produced by the original vertex.
+    Vertex,
+}
+
 /// Cantellate the mesh.
-/// The cantellation factor is an absolute distance from the original face to the cantellated face.
-/// The epsilon is a small value to compare floating point numbers.
-pub fn cantellate<N>(mesh: &Mesh<N>, factor: N, epsilon: N) -> Mesh<N>
+pub fn cantellate<N>(mesh: &Mesh<N>, params: &CantellateParams<N>) -> Mesh<N>
 where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
-    let mut result_mesh = Mesh::default();
+    cantellate_selected(mesh, params, &vec![true; mesh.faces.len()]).mesh
+}
+
+/// Cantellate only selected faces, the selection is a flag of each face.
+/// Unselected faces stay in place and keep their vertices shared,
+/// edges between selected and unselected faces become transition strips.
+pub fn cantellate_selected<N>(
+    mesh: &Mesh<N>,
+    params: &CantellateParams<N>,
+    selected: &[bool],
+) -> Cantellated<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    // calculate the normal of each face, degenerate and non-planar faces get fallback normals
+    let FaceNormals {
+        normals: faces_normal,
+        report,
+    } = FaceNormals::new(mesh, &params.tolerances);
 
-    // calculate the normal of each face.
-    let faces_normal = get_faces_normal(mesh, epsilon);
+    let mut result = Cantellated {
+        mesh: Mesh::default(),
+        selected: Vec::new(),
+        kinds: Vec::new(),
+        normals: report,
+    };
+    let is_selected = |face_index: usize| selected.get(face_index).copied().unwrap_or(false);
 
     let mut cantellated_vertices: Vec<_> = (0..mesh.vertices.len())
         .map(CantellatedVertex::new)
@@ -24,47 +162,90 @@ where
     // fill the map from the vertex to their faces
     fill_vertices_fo_faces(mesh, &mut cantellated_vertices);
 
+    // rounded mode shares arcs between vertex caps and edge strips
+    let mut rounding = if params.segments > 1 && params.placement == Placement::Normal {
+        Some(Rounding::new(params))
+    } else {
+        None
+    };
+
     // cantellate the vertices
     // this operation expands all vertices by their faces
-    // all vertices of result mesh are create here, expanded edges and faces are created later
+    // all corner vertices of result mesh are create here, expanded edges and faces are created later
     // using the information about the cantellated vertices
     cantellate_vertices(
         mesh,
-        &mut result_mesh,
+        &mut result,
         &mut cantellated_vertices,
         &faces_normal,
-        factor,
-        epsilon,
+        params,
+        &is_selected,
+        rounding.as_mut(),
+    );
+
+    cantellate_edges(
+        mesh,
+        &mut result,
+        &cantellated_vertices,
+        &is_selected,
+        rounding.as_mut(),
     );
 
-    cantellate_edges(mesh, &mut result_mesh, &cantellated_vertices);
+    cantellate_faces(mesh, &mut result, &cantellated_vertices, &is_selected);
 
-    cantellate_faces(mesh, &mut result_mesh, &cantellated_vertices);
+    result
+}
 
-    result_mesh
+impl<N> Cantellated<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Mark faces added to the result mesh after the last call.
+    fn mark(&mut self, selected: bool, kind: FaceKind) {
+        self.selected.resize(self.mesh.faces.len(), selected);
+        self.kinds.resize(self.mesh.faces.len(), kind);
+    }
 }
 
 fn cantellate_vertices<N>(
     mesh: &Mesh<N>,
-    result_mesh: &mut Mesh<N>,
+    result: &mut Cantellated<N>,
     cantellated_vertices: &mut [CantellatedVertex],
     faces_normal: &[Option<Vec3<N>>],
-    factor: N,
-    epsilon: N,
+    params: &CantellateParams<N>,
+    is_selected: &impl Fn(usize) -> bool,
+    mut rounding: Option<&mut Rounding<N>>,
 ) where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
     cantellated_vertices
         .iter_mut()
         .for_each(|cantellated_vertex| {
-            cantellated_vertex.cantellate(faces_normal, factor, epsilon, mesh, result_mesh);
+            let caps = cantellated_vertex.cantellate(
+                faces_normal,
+                params,
+                is_selected,
+                mesh,
+                &mut result.mesh,
+            );
+            for cap in caps {
+                match rounding.as_deref_mut() {
+                    Some(rounding) => rounding.cap(mesh, &mut result.mesh, cantellated_vertex, cap),
+                    None => result.mesh.faces.push(cap),
+                }
+            }
+            result.mark(
+                cantellated_vertex.faces.iter().all(|&f| is_selected(f)),
+                FaceKind::Vertex,
+            );
         });
 }
 
 fn cantellate_faces<N>(
     mesh: &Mesh<N>,
-    result_mesh: &mut Mesh<N>,
+    result: &mut Cantellated<N>,
     cantellated_vertices: &[CantellatedVertex],
+    is_selected: &impl Fn(usize) -> bool,
 ) where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
@@ -82,15 +263,18 @@ fn cantellate_faces<N>(
             }
         }
         if cantellated_face.len() > 2 {
-            result_mesh.faces.push(cantellated_face);
+            result.mesh.faces.push(cantellated_face);
+            result.mark(is_selected(face_index), FaceKind::Face);
         }
     }
 }
 
 fn cantellate_edges<N>(
     mesh: &Mesh<N>,
-    result_mesh: &mut Mesh<N>,
+    result: &mut Cantellated<N>,
     cantellated_vertices: &[CantellatedVertex],
+    is_selected: &impl Fn(usize) -> bool,
+    mut rounding: Option<&mut Rounding<N>>,
 ) where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
@@ -106,7 +290,7 @@ fn cantellate_edges<N>(
 
     // Second pass. Cantellate the edges.
     for (face_index, face) in mesh.faces.iter().enumerate() {
-        if !is_face_valid(face) {
+        if !is_face_valid(face, mesh.vertices.len()) {
             continue;
         }
 
@@ -131,9 +315,21 @@ fn cantellate_edges<N>(
                     another_face_index,
                 );
 
-                if let Some(edge_face) = edge_face {
-                    result_mesh.faces.push(edge_face);
+                match (edge_face, rounding.as_deref_mut()) {
+                    (Some(_), Some(rounding)) => rounding.edge_strip(
+                        mesh,
+                        &mut result.mesh,
+                        cantellated_vertices,
+                        (v1, v2),
+                        (face_index, another_face_index),
+                    ),
+                    (Some(edge_face), None) => result.mesh.faces.push(edge_face),
+                    (None, _) => {}
                 }
+                result.mark(
+                    is_selected(face_index) && is_selected(another_face_index),
+                    FaceKind::Edge,
+                );
             }
         }
     }
@@ -187,7 +383,7 @@ where
         .iter()
         .enumerate()
         .for_each(|(face_index, face)| {
-            if !is_face_valid(face) {
+            if !is_face_valid(face, mesh.vertices.len()) {
                 return;
             }
             face.iter().for_each(|&vertex_index| {
@@ -200,18 +396,20 @@ where
 }
 
 /// Calculate the normal of each face.
-fn get_faces_normal<N>(mesh: &Mesh<N>, epsilon: N) -> Vec<Option<Vec3<N>>>
+/// The epsilon is the minimal length of the cross product of face edges, see `Tolerances::face_normal`.
+pub(crate) fn get_faces_normal<N>(mesh: &Mesh<N>, epsilon: N) -> Vec<Option<Vec3<N>>>
 where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
     (0..mesh.faces.len())
         .map(|face_index| {
             let face = &mesh.faces[face_index];
-            let points: Vec<_> = face
+            // face with invalid indices has no normal
+            let points: Option<Vec<_>> = face
                 .iter()
-                .map(|&vertex_index| mesh.vertices[vertex_index])
+                .map(|&vertex_index| mesh.vertices.get(vertex_index).copied())
                 .collect();
-            find_face_normal(points, epsilon)
+            find_face_normal(points?, epsilon)
         })
         .collect()
 }
@@ -242,9 +440,90 @@ where
     normal.normalize(epsilon)
 }
 
-// face is valid if it has at least 3 vertices and all vertices are different
-fn is_face_valid(face: &[usize]) -> bool {
+/// Calculate the offset of the cantellated vertex from the original vertex for the face corner.
+/// Returns `None` if the corner is not moved because the face is degenerated.
+fn get_corner_offset<N>(
+    mesh: &Mesh<N>,
+    faces_normal: &[Option<Vec3<N>>],
+    face_index: usize,
+    vertex_index: usize,
+    params: &CantellateParams<N>,
+) -> Option<Vec3<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let face = &mesh.faces[face_index];
+    let vertex = mesh.vertices[vertex_index];
+    match params.placement {
+        Placement::Normal => Some(faces_normal[face_index]? * params.factor),
+        Placement::Centroid => {
+            let centroid = face
+                .iter()
+                .fold(Vec3::zero(), |sum, &index| sum + mesh.vertices[index])
+                / N::from_usize(face.len())?;
+            let to_centroid = centroid - vertex;
+            let distance = to_centroid.length();
+            // do not move the vertex beyond the centroid
+            Some(to_centroid.normalize(params.tolerances.normal)? * params.factor.min(distance))
+        }
+        Placement::Inset => get_inset_offset(
+            mesh,
+            faces_normal,
+            face_index,
+            vertex_index,
+            params.factor,
+            &params.tolerances,
+        ),
+        Placement::InsetNormal => {
+            let inset = get_inset_offset(
+                mesh,
+                faces_normal,
+                face_index,
+                vertex_index,
+                params.inset,
+                &params.tolerances,
+            )?;
+            Some(inset + faces_normal[face_index]? * params.factor)
+        }
+    }
+}
+
+/// Calculate the in-plane offset of the face corner so that both corner edges are moved inside the face by the distance.
+fn get_inset_offset<N>(
+    mesh: &Mesh<N>,
+    faces_normal: &[Option<Vec3<N>>],
+    face_index: usize,
+    vertex_index: usize,
+    distance: N,
+    tolerances: &Tolerances<N>,
+) -> Option<Vec3<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let face = &mesh.faces[face_index];
+    let normal = faces_normal[face_index]?;
+    let position = face.iter().position(|&v| v == vertex_index)?;
+    let prev = mesh.vertices[face[(position + face.len() - 1) % face.len()]];
+    let next = mesh.vertices[face[(position + 1) % face.len()]];
+    let vertex = mesh.vertices[vertex_index];
+
+    // inward directions of the corner edges, the face is counter-clockwise around the normal
+    let a = normal.cross(vertex - prev).normalize(tolerances.normal)?;
+    let b = normal.cross(next - vertex).normalize(tolerances.normal)?;
+
+    // miter of the corner, for the hairpin corner the miter is infinite and the first edge is used
+    let denominator = N::one() + a.dot(b);
+    if denominator < tolerances.angle {
+        Some(a * distance)
+    } else {
+        Some((a + b) * (distance / denominator))
+    }
+}
+
+// face is valid if it has at least 3 vertices, all vertices exist and are different
+fn is_face_valid(face: &[usize], vertices_count: usize) -> bool {
     face.len() > 2
+        && face.iter().all(|&v| v < vertices_count)
         && (1..face.len()).all(|i| {
             let vertex = face[i - 1];
             face.iter().skip(i).all(|&v| v != vertex)
@@ -274,15 +553,16 @@ impl CantellatedVertex {
         }
     }
 
-    /// Expand the vertex and create a face of the expanded vertex.
+    /// Expand the vertex and return a face of the expanded vertex.
     fn cantellate<N>(
         &mut self,
         faces_normal: &[Option<Vec3<N>>],
-        factor: N,
-        epsilon: N,
+        params: &CantellateParams<N>,
+        is_selected: &impl Fn(usize) -> bool,
         mesh: &Mesh<N>,
         result_mesh: &mut Mesh<N>,
-    ) where
+    ) -> Vec<SmallVec<usize>>
+    where
         N: Float + ToPrimitive + FromPrimitive + Default,
     {
         let vertex = mesh.vertices[self.index];
@@ -290,18 +570,48 @@ impl CantellatedVertex {
         // If the vertex is not part of any face, then it is a single point.
         if self.faces.is_empty() {
             result_mesh.vertices.push(vertex);
-            return;
+            return Vec::new();
         }
 
         let is_watertight = self.sort_faces(mesh);
+        // squared distances are compared without the square root
+        let merge_squared = params.tolerances.merge * params.tolerances.merge;
 
-        let mut face = SmallVec::<usize>::new();
-        for &face_index in &self.faces {
-            let cantellated_vertex = if let Some(face_normal) = faces_normal[face_index] {
-                vertex + face_normal * factor
-            } else {
-                vertex
+        // faces joined by smooth edges share the cantellated vertex, it's placed by the mean offset of the group
+        let groups = self.groups(mesh, faces_normal, params, is_selected);
+        let mut groups_offset: SmallVec<(Vec3<N>, usize)> =
+            (0..self.faces.len()).map(|_| (Vec3::zero(), 0)).collect();
+        let offsets: SmallVec<Vec3<N>> = self
+            .faces
+            .iter()
+            .zip(&groups)
+            .map(|(&face_index, &group)| {
+                // unselected faces are not moved
+                let offset = if is_selected(face_index) {
+                    get_corner_offset(mesh, faces_normal, face_index, self.index, params)
+                        .unwrap_or(Vec3::zero())
+                } else {
+                    Vec3::zero()
+                };
+                let (sum, count) = &mut groups_offset[group];
+                *sum = *sum + offset;
+                *count += 1;
+                offset
+            })
+            .collect();
+        let mut groups_corner: SmallVec<Option<usize>> =
+            (0..self.faces.len()).map(|_| None).collect();
+
+        for (&group, offset) in groups.iter().zip(offsets) {
+            if let Some(corner) = groups_corner[group] {
+                self.cantellated.push(corner);
+                continue;
+            }
+            let offset = match groups_offset[group] {
+                (sum, count) if count > 1 => sum / N::from_usize(count).unwrap_or_else(N::one),
+                _ => offset,
             };
+            let cantellated_vertex = vertex + offset;
 
             // it's needed to decide if the vertex is the same as a neighbour vertex
             // it is needed to avoid duplicate vertices and avoid zero-length edges
@@ -309,7 +619,7 @@ impl CantellatedVertex {
                 // check with the previous vertex as a constructed neightbour
                 let prev_vertex = result_mesh.vertices[prev_index];
                 let diff = cantellated_vertex - prev_vertex;
-                let same_neighbour = if diff.length() < epsilon {
+                let same_neighbour = if diff.dot(diff) < merge_squared {
                     Some(prev_index)
                 } else {
                     None
@@ -320,7 +630,7 @@ impl CantellatedVertex {
                     let first_index = self.cantellated[0];
                     let first_vertex = result_mesh.vertices[first_index];
                     let diff = cantellated_vertex - first_vertex;
-                    if diff.length() < epsilon {
+                    if diff.dot(diff) < merge_squared {
                         Some(first_index)
                     } else {
                         None
@@ -341,14 +651,16 @@ impl CantellatedVertex {
                 // create a new vertex in the result mesh
                 let new_vertex_index = result_mesh.vertices.len();
                 self.cantellated.push(new_vertex_index);
-                face.push(new_vertex_index);
                 result_mesh.vertices.push(cantellated_vertex);
             }
+            groups_corner[group] = self.cantellated.last().copied();
         }
 
         // non-watertight vertex has no cantellated face
-        if is_watertight && face.len() > 2 {
-            result_mesh.faces.push(face);
+        if is_watertight {
+            cap_loops(&self.cantellated)
+        } else {
+            Vec::new()
         }
     }
 
@@ -422,6 +734,72 @@ impl CantellatedVertex {
         }
     }
 
+    /// Group index of each vertex face, faces of the same group share the cantellated vertex.
+    /// Neighbour faces joined by an edge smoother than `min_angle` are in the same group,
+    /// all unselected faces are in the same group.
+    /// Faces should be sorted around the vertex.
+    fn groups<N>(
+        &self,
+        mesh: &Mesh<N>,
+        faces_normal: &[Option<Vec3<N>>],
+        params: &CantellateParams<N>,
+        is_selected: &impl Fn(usize) -> bool,
+    ) -> SmallVec<usize>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let mut groups: SmallVec<usize> = (0..self.faces.len()).collect();
+        let mut unselected = (0..self.faces.len()).filter(|&i| !is_selected(self.faces[i]));
+        if let Some(first) = unselected.next() {
+            unselected.for_each(|i| groups[i] = first);
+        }
+        if params.min_angle <= N::zero() || self.faces.len() < 2 {
+            return groups;
+        }
+
+        let min_cos = params.min_angle.cos();
+        // neighbours around the vertex of the face: the next vertex and the previous vertex
+        let around = |face_index: usize| {
+            let face = &mesh.faces[face_index];
+            let position = face.iter().position(|&v| v == self.index)?;
+            Some((
+                face[(position + 1) % face.len()],
+                face[(position + face.len() - 1) % face.len()],
+            ))
+        };
+        let is_smooth = |a: usize, b: usize| {
+            let (Some((a_next, a_prev)), Some((b_next, b_prev))) = (around(a), around(b)) else {
+                return false;
+            };
+            let (Some(a_normal), Some(b_normal)) = (faces_normal[a], faces_normal[b]) else {
+                return false;
+            };
+            (a_prev == b_next || a_next == b_prev)
+                && is_selected(a)
+                && is_selected(b)
+                && a_normal.dot(b_normal) > min_cos
+        };
+        let join = |groups: &mut SmallVec<usize>, from: usize, to: usize| {
+            let (from, to) = (groups[from], groups[to]);
+            groups
+                .iter_mut()
+                .filter(|group| **group == from)
+                .for_each(|group| *group = to);
+        };
+
+        for i in 1..self.faces.len() {
+            if is_smooth(self.faces[i - 1], self.faces[i]) {
+                join(&mut groups, i, i - 1);
+            }
+        }
+        // close the cycle around the vertex
+        let last = self.faces.len() - 1;
+        if self.faces.len() > 2 && is_smooth(self.faces[last], self.faces[0]) {
+            join(&mut groups, last, 0);
+        }
+        groups
+    }
+
     /// find the cantellated vertex by face.
     fn get_cantellated_vertex_by_face(&self, face_index: usize) -> Option<usize> {
         self.faces.iter().zip(self.cantellated.iter()).find_map(
@@ -435,3 +813,304 @@ impl CantellatedVertex {
         )
     }
 }
+
+/// Help structure to build rounded edge strips and vertex caps.
+/// New vertices are interpolated between offsets of the corners from the original vertex:
+/// the direction goes along the circular arc and the length changes linearly.
+/// It's used only for offsets along face normals, so all new vertices lie on the sphere around the original vertex.
+/// Corners shared by faces joined by smooth edges have shorter mean offsets, so their arcs leave the sphere.
+struct Rounding<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    segments: usize,
+    tolerances: Tolerances<N>,
+
+    /// Arcs between two corners of the same expanded vertex. Arc includes both corners.
+    /// Key is a pair of sorted corner indices in the result mesh, the arc goes from the first corner to the second.
+    arcs: AHashMap<(usize, usize), SmallVec<usize>>,
+}
+
+impl<N> Rounding<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    fn new(params: &CantellateParams<N>) -> Self {
+        Self {
+            segments: params.segments,
+            tolerances: params.tolerances,
+            arcs: AHashMap::new(),
+        }
+    }
+
+    /// Create the rounded edge strip between two faces of the original mesh.
+    /// The strip is a sequence of faces between arcs of the both edge vertices.
+    fn edge_strip(
+        &mut self,
+        mesh: &Mesh<N>,
+        result_mesh: &mut Mesh<N>,
+        cantellated_vertices: &[CantellatedVertex],
+        (v1, v2): (usize, usize),
+        (face1, face2): (usize, usize),
+    ) {
+        let corner = |vertex: usize, face: usize| {
+            cantellated_vertices
+                .get(vertex)?
+                .get_cantellated_vertex_by_face(face)
+        };
+        let (Some(e1), Some(e2), Some(e3), Some(e4)) = (
+            corner(v1, face2),
+            corner(v2, face2),
+            corner(v2, face1),
+            corner(v1, face1),
+        ) else {
+            return;
+        };
+
+        let arc1 = self.arc(result_mesh, mesh.vertices[v1], e4, e1);
+        let arc2 = self.arc(result_mesh, mesh.vertices[v2], e3, e2);
+        for k in 0..self.segments {
+            push_face(
+                result_mesh,
+                [arc1[k + 1], arc2[k + 1], arc2[k], arc1[k]].into_iter(),
+            );
+        }
+    }
+
+    /// Create the spherical patch instead of the flat vertex cap.
+    /// The patch is a fan of sectors from the cap center to each pair of neighbour corners,
+    /// each sector is a triangular grid with `segments` rows.
+    fn cap(
+        &mut self,
+        mesh: &Mesh<N>,
+        result_mesh: &mut Mesh<N>,
+        cantellated_vertex: &CantellatedVertex,
+        flat_cap: SmallVec<usize>,
+    ) {
+        let vertex = mesh.vertices[cantellated_vertex.index];
+        let offsets: SmallVec<Vec3<N>> = flat_cap
+            .iter()
+            .map(|&corner| result_mesh.vertices[corner] - vertex)
+            .collect();
+
+        // the center offset has a mean direction and a mean length of the corner offsets
+        let center_offset = offsets
+            .iter()
+            .try_fold(Vec3::zero(), |sum, offset| {
+                Some(sum + offset.normalize(self.tolerances.normal)?)
+            })
+            .and_then(|direction| direction.normalize(self.tolerances.angle))
+            .map(|direction| {
+                let length = offsets
+                    .iter()
+                    .fold(N::zero(), |sum, offset| sum + offset.length());
+                direction * (length / N::from_usize(offsets.len()).unwrap_or_default())
+            });
+
+        // offsets of spokes from the cap center to each corner
+        let spokes_offset: Option<Vec<SmallVec<Vec3<N>>>> = center_offset.and_then(|center| {
+            offsets
+                .iter()
+                .map(|&offset| {
+                    (0..=self.segments)
+                        .map(|r| {
+                            interpolate_offset(
+                                center,
+                                offset,
+                                self.fraction(r, self.segments),
+                                &self.tolerances,
+                            )
+                        })
+                        .collect()
+                })
+                .collect()
+        });
+
+        // corner is not moved or directions are opposite, there is no sphere to round the cap
+        let Some(spokes_offset) = spokes_offset else {
+            result_mesh.faces.push(flat_cap);
+            return;
+        };
+
+        let center = result_mesh.vertices.len();
+        result_mesh.vertices.push(vertex + spokes_offset[0][0]);
+        let spokes: Vec<SmallVec<usize>> = flat_cap
+            .iter()
+            .zip(spokes_offset.iter())
+            .map(|(&corner, offsets)| {
+                let mut spoke = SmallVec::new();
+                spoke.push(center);
+                for &offset in &offsets[1..self.segments] {
+                    spoke.push(result_mesh.vertices.len());
+                    result_mesh.vertices.push(vertex + offset);
+                }
+                spoke.push(corner);
+                spoke
+            })
+            .collect();
+
+        for i in 0..flat_cap.len() {
+            let j = (i + 1) % flat_cap.len();
+            let arc = self.arc(result_mesh, vertex, flat_cap[i], flat_cap[j]);
+
+            // rows of the sector grid from the center to the arc
+            let mut rows: Vec<SmallVec<usize>> = Vec::with_capacity(self.segments + 1);
+            for r in 0..=self.segments {
+                let row = if r == 0 {
+                    [center].into_iter().collect()
+                } else if r == self.segments {
+                    arc.clone()
+                } else {
+                    let mut row = SmallVec::new();
+                    row.push(spokes[i][r]);
+                    for k in 1..r {
+                        let (a, b) = (spokes_offset[i][r], spokes_offset[j][r]);
+                        let offset =
+                            interpolate_offset(a, b, self.fraction(k, r), &self.tolerances)
+                                .unwrap_or(a);
+                        row.push(result_mesh.vertices.len());
+                        result_mesh.vertices.push(vertex + offset);
+                    }
+                    row.push(spokes[j][r]);
+                    row
+                };
+                rows.push(row);
+            }
+
+            for r in 0..self.segments {
+                for k in 0..=r {
+                    push_face(
+                        result_mesh,
+                        [rows[r][k], rows[r + 1][k], rows[r + 1][k + 1]].into_iter(),
+                    );
+                    if k < r {
+                        push_face(
+                            result_mesh,
+                            [rows[r][k], rows[r + 1][k + 1], rows[r][k + 1]].into_iter(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get or create the arc between two corners of the same expanded vertex.
+    fn arc(
+        &mut self,
+        result_mesh: &mut Mesh<N>,
+        vertex: Vec3<N>,
+        from: usize,
+        to: usize,
+    ) -> SmallVec<usize> {
+        // corners are the same, arc is collapsed into a single vertex
+        if from == to {
+            return (0..=self.segments).map(|_| from).collect();
+        }
+
+        let key = (from.min(to), from.max(to));
+        let mut arc = if let Some(arc) = self.arcs.get(&key) {
+            arc.clone()
+        } else {
+            let a = result_mesh.vertices[key.0] - vertex;
+            let b = result_mesh.vertices[key.1] - vertex;
+            let mut arc = SmallVec::new();
+            arc.push(key.0);
+            for k in 1..self.segments {
+                let offset =
+                    interpolate_offset(a, b, self.fraction(k, self.segments), &self.tolerances);
+                if let Some(offset) = offset {
+                    arc.push(result_mesh.vertices.len());
+                    result_mesh.vertices.push(vertex + offset);
+                } else {
+                    // there is no arc between opposite or zero offsets, degenerate it into the corner
+                    arc.push(key.0);
+                }
+            }
+            arc.push(key.1);
+            self.arcs.insert(key, arc.clone());
+            arc
+        };
+
+        if from != key.0 {
+            arc.reverse();
+        }
+        arc
+    }
+
+    fn fraction(&self, numerator: usize, denominator: usize) -> N {
+        N::from_usize(numerator).unwrap_or_default()
+            / N::from_usize(denominator).unwrap_or_default()
+    }
+}
+
+/// Interpolate between two offsets along the circular arc.
+/// The direction is a spherical linear interpolation and the length is a linear interpolation.
+/// Returns `None` if offsets are zero or opposite and the arc between them is not defined.
+fn interpolate_offset<N>(
+    a: Vec3<N>,
+    b: Vec3<N>,
+    t: N,
+    tolerances: &Tolerances<N>,
+) -> Option<Vec3<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let (a_length, b_length) = (a.length(), b.length());
+    let (a, b) = (
+        a.normalize(tolerances.normal)?,
+        b.normalize(tolerances.normal)?,
+    );
+    let length = a_length + (b_length - a_length) * t;
+
+    let cos = a.dot(b).max(-N::one()).min(N::one());
+    let angle = cos.acos();
+    let sin = angle.sin();
+    if sin < tolerances.angle.sin() {
+        return if cos > N::zero() {
+            Some(a * length)
+        } else {
+            None
+        };
+    }
+    Some((a * ((N::one() - t) * angle).sin() + b * (t * angle).sin()) / sin * length)
+}
+
+/// Faces of the vertex cap by corners around the vertex.
+/// Repeated neighbour corners are merged. If the same corner is used by non-neighbour faces,
+/// e.g. by unselected faces, the cap is split into loops by the corner and degenerate loops are removed.
+fn cap_loops(corners: &[usize]) -> Vec<SmallVec<usize>> {
+    let mut loops = Vec::new();
+    let mut path = SmallVec::<usize>::new();
+    for &corner in corners {
+        if path.last() == Some(&corner) {
+            continue;
+        }
+        // the loop from the previous visit of the corner is closed
+        if let Some(position) = path.iter().position(|&c| c == corner) {
+            loops.push(path.drain(position..).collect());
+        }
+        path.push(corner);
+    }
+    loops.push(path);
+    loops.retain(|l| l.len() > 2);
+    loops
+}
+
+/// Push the face without repeated neighbour vertices if it's not degenerated.
+fn push_face<N>(result_mesh: &mut Mesh<N>, vertices: impl Iterator<Item = usize>)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut face = SmallVec::<usize>::new();
+    for vertex in vertices {
+        if face.last() != Some(&vertex) {
+            face.push(vertex);
+        }
+    }
+    while face.len() > 1 && face.first() == face.last() {
+        face.pop();
+    }
+    if face.len() > 2 {
+        result_mesh.faces.push(face);
+    }
+}
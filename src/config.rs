@@ -0,0 +1,290 @@
+use crate::{CantellateArgs, CommonOptions};
+use clap::{parser::ValueSource, ArgMatches, ValueEnum};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Description of the cantellation run loaded from a TOML or JSON file.
+/// Names of fields are the same as names of command line options.
+/// Options set in the command line override values of the file, and values of the file override the preset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunConfig {
+    /// Name of the built-in preset used as a base of the file.
+    pub preset: Option<String>,
+
+    pub input: Option<String>,
+    pub output: Option<String>,
+    pub recursive: Option<bool>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub output_name: Option<String>,
+    pub format: Option<String>,
+    pub output_faces: Option<String>,
+    pub on_existing: Option<String>,
+    pub jobs: Option<usize>,
+    pub save_every: Option<usize>,
+    pub uncantellate: Option<bool>,
+    pub repair: Option<bool>,
+
+    pub factor: Option<f32>,
+    pub placement: Option<String>,
+    pub inset: Option<f32>,
+    pub segments: Option<usize>,
+    pub min_angle: Option<f32>,
+    pub count: Option<usize>,
+    pub merge_coplanar: Option<f32>,
+    pub weld: Option<f32>,
+    pub subdivide: Option<usize>,
+    pub subdivision: Option<String>,
+    pub frame: Option<f32>,
+    pub hull: Option<bool>,
+    pub select_faces: Option<Vec<usize>>,
+    pub select_group: Option<String>,
+    pub select_box: Option<Vec<f32>>,
+    pub select_normal: Option<Vec<f32>>,
+
+    pub epsilon: Option<f32>,
+    pub normal_tolerance: Option<f32>,
+    pub merge_tolerance: Option<f32>,
+    pub planarity_tolerance: Option<f32>,
+    pub angle_tolerance: Option<f32>,
+    pub fit_plane_tolerance: Option<f32>,
+    pub relative_tolerances: Option<bool>,
+    pub precision: Option<String>,
+}
+
+impl RunConfig {
+    /// Names of built-in presets.
+    pub const PRESETS: &'static [&'static str] = &["print-ready", "game-asset"];
+
+    /// Built-in preset by the name.
+    pub fn preset(name: &str) -> Result<Self, String> {
+        match name {
+            // chamfered edges without growing the model, repaired and saved for slicers
+            // inset corners have no common center, so edges are not rounded
+            "print-ready" => Ok(Self {
+                repair: Some(true),
+                factor: Some(0.1),
+                placement: Some("inset".to_owned()),
+                segments: Some(1),
+                format: Some("stl".to_owned()),
+                output_faces: Some("tri".to_owned()),
+                ..Default::default()
+            }),
+            // a single small bevel to catch highlights, low faces count
+            "game-asset" => Ok(Self {
+                repair: Some(true),
+                factor: Some(0.02),
+                placement: Some("inset".to_owned()),
+                segments: Some(1),
+                format: Some("obj".to_owned()),
+                output_faces: Some("tri".to_owned()),
+                ..Default::default()
+            }),
+            _ => Err(format!(
+                "Unknown preset `{name}`, expected one of: {}",
+                Self::PRESETS.join(", ")
+            )),
+        }
+    }
+
+    /// Load the config file, the format is detected by the extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to load config {}: {e}", path.display()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&text)
+                .map_err(|e| format!("Failed to parse config {}: {e}", path.display())),
+            Some("toml") => toml::from_str(&text)
+                .map_err(|e| format!("Failed to parse config {}: {e}", path.display())),
+            _ => Err(format!(
+                "Unsupported config format of {}, expected `.toml` or `.json`",
+                path.display()
+            )),
+        }
+    }
+
+    /// Apply values of the config over the preset.
+    /// The preset is chosen by the name or by the `preset` field of the config.
+    pub fn with_preset(self, preset: Option<&str>) -> Result<Self, String> {
+        match preset.or(self.preset.as_deref()) {
+            Some(preset) => Ok(Self::preset(preset)?.merge(self)),
+            None => Ok(self),
+        }
+    }
+
+    /// Values of the other config override values of this one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            preset: other.preset.or(self.preset),
+            input: other.input.or(self.input),
+            output: other.output.or(self.output),
+            recursive: other.recursive.or(self.recursive),
+            include: other.include.or(self.include),
+            exclude: other.exclude.or(self.exclude),
+            output_name: other.output_name.or(self.output_name),
+            format: other.format.or(self.format),
+            output_faces: other.output_faces.or(self.output_faces),
+            on_existing: other.on_existing.or(self.on_existing),
+            jobs: other.jobs.or(self.jobs),
+            save_every: other.save_every.or(self.save_every),
+            uncantellate: other.uncantellate.or(self.uncantellate),
+            repair: other.repair.or(self.repair),
+            factor: other.factor.or(self.factor),
+            placement: other.placement.or(self.placement),
+            inset: other.inset.or(self.inset),
+            segments: other.segments.or(self.segments),
+            min_angle: other.min_angle.or(self.min_angle),
+            count: other.count.or(self.count),
+            merge_coplanar: other.merge_coplanar.or(self.merge_coplanar),
+            weld: other.weld.or(self.weld),
+            subdivide: other.subdivide.or(self.subdivide),
+            subdivision: other.subdivision.or(self.subdivision),
+            frame: other.frame.or(self.frame),
+            hull: other.hull.or(self.hull),
+            select_faces: other.select_faces.or(self.select_faces),
+            select_group: other.select_group.or(self.select_group),
+            select_box: other.select_box.or(self.select_box),
+            select_normal: other.select_normal.or(self.select_normal),
+            epsilon: other.epsilon.or(self.epsilon),
+            normal_tolerance: other.normal_tolerance.or(self.normal_tolerance),
+            merge_tolerance: other.merge_tolerance.or(self.merge_tolerance),
+            planarity_tolerance: other.planarity_tolerance.or(self.planarity_tolerance),
+            angle_tolerance: other.angle_tolerance.or(self.angle_tolerance),
+            fit_plane_tolerance: other.fit_plane_tolerance.or(self.fit_plane_tolerance),
+            relative_tolerances: other.relative_tolerances.or(self.relative_tolerances),
+            precision: other.precision.or(self.precision),
+        }
+    }
+
+    /// Set arguments which are not given in the command line.
+    pub fn apply(
+        self,
+        args: &mut CantellateArgs,
+        common: &mut CommonOptions,
+        matches: &ArgMatches,
+    ) -> Result<(), String> {
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        macro_rules! set {
+            ($target:expr, $id:literal, $value:expr) => {
+                if let Some(value) = $value {
+                    if !from_command_line($id) {
+                        $target = value;
+                    }
+                }
+            };
+        }
+
+        set!(args.input, "input", self.input);
+        set!(args.output, "output", self.output);
+        set!(args.recursive, "recursive", self.recursive);
+        set!(args.include, "include", self.include);
+        set!(args.exclude, "exclude", self.exclude);
+        set!(args.output_name, "output_name", self.output_name);
+        set!(args.jobs, "jobs", self.jobs);
+        set!(args.save_every, "save_every", self.save_every.map(Some));
+        set!(args.uncantellate, "uncantellate", self.uncantellate);
+        set!(args.repair, "repair", self.repair);
+        set!(
+            args.format,
+            "format",
+            parse_enum(self.format, "format")?.map(Some)
+        );
+        set!(
+            args.output_faces,
+            "output_faces",
+            parse_enum(self.output_faces, "output-faces")?
+        );
+        set!(
+            args.on_existing,
+            "on_existing",
+            parse_enum(self.on_existing, "on-existing")?.map(Some)
+        );
+
+        set!(args.options.factor, "factor", self.factor);
+        set!(
+            args.options.placement,
+            "placement",
+            parse_enum(self.placement, "placement")?
+        );
+        set!(args.options.inset, "inset", self.inset.map(Some));
+        set!(args.options.segments, "segments", self.segments);
+        set!(args.options.min_angle, "min_angle", self.min_angle);
+        set!(args.options.count, "count", self.count);
+        set!(
+            args.options.merge_coplanar,
+            "merge_coplanar",
+            self.merge_coplanar.map(Some)
+        );
+        set!(args.options.weld, "weld", self.weld.map(Some));
+        set!(args.options.subdivide, "subdivide", self.subdivide);
+        set!(
+            args.options.subdivision,
+            "subdivision",
+            parse_enum(self.subdivision, "subdivision")?
+        );
+        set!(args.options.frame, "frame", self.frame.map(Some));
+        set!(args.options.hull, "hull", self.hull);
+        set!(args.options.select_faces, "select_faces", self.select_faces);
+        set!(
+            args.options.select_group,
+            "select_group",
+            self.select_group.map(Some)
+        );
+        set!(args.options.select_box, "select_box", self.select_box);
+        set!(
+            args.options.select_normal,
+            "select_normal",
+            self.select_normal
+        );
+
+        set!(common.epsilon, "epsilon", self.epsilon);
+        set!(
+            common.normal_tolerance,
+            "normal_tolerance",
+            self.normal_tolerance.map(Some)
+        );
+        set!(
+            common.merge_tolerance,
+            "merge_tolerance",
+            self.merge_tolerance.map(Some)
+        );
+        set!(
+            common.planarity_tolerance,
+            "planarity_tolerance",
+            self.planarity_tolerance.map(Some)
+        );
+        set!(
+            common.angle_tolerance,
+            "angle_tolerance",
+            self.angle_tolerance.map(Some)
+        );
+        set!(
+            common.fit_plane_tolerance,
+            "fit_plane_tolerance",
+            self.fit_plane_tolerance.map(Some)
+        );
+        set!(
+            common.relative_tolerances,
+            "relative_tolerances",
+            self.relative_tolerances
+        );
+        set!(
+            common.precision,
+            "precision",
+            parse_enum(self.precision, "precision")?
+        );
+        Ok(())
+    }
+}
+
+/// Parse the value by the same names as in the command line.
+fn parse_enum<T: ValueEnum>(value: Option<String>, name: &str) -> Result<Option<T>, String> {
+    value
+        .map(|value| {
+            T::from_str(&value, true).map_err(|e| format!("Invalid {name} in config: {e}"))
+        })
+        .transpose()
+}
@@ -0,0 +1,30 @@
+use ahash::AHashMap;
+
+/// Union-find structure over indices, e.g. vertices or faces.
+#[derive(Default)]
+pub(crate) struct DisjointSet {
+    parents: AHashMap<usize, usize>,
+}
+
+impl DisjointSet {
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while let Some(&parent) = self.parents.get(&root) {
+            root = parent;
+        }
+        // compress the path to the root
+        let mut current = index;
+        while let Some(&parent) = self.parents.get(&current) {
+            self.parents.insert(current, root);
+            current = parent;
+        }
+        root
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents.insert(a.max(b), a.min(b));
+        }
+    }
+}
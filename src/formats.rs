@@ -0,0 +1,405 @@
+use crate::{mesh::Mesh, vec3::Vec3};
+use ahash::AHashMap;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use std::{
+    fmt::{Display, Write as _},
+    path::Path,
+    str::FromStr,
+};
+
+/// Supported mesh file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Wavefront `.obj`.
+    Obj,
+    /// Object file format `.off`.
+    Off,
+    /// Stereolithography `.stl`, binary or ASCII on load and binary on save.
+    Stl,
+    /// Polygon file format `.ply`, ASCII only.
+    Ply,
+}
+
+impl Format {
+    /// Extensions of all supported formats.
+    pub const EXTENSIONS: &'static [&'static str] = &["obj", "off", "stl", "ply"];
+
+    /// Extension of files in the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Obj => "obj",
+            Format::Off => "off",
+            Format::Stl => "stl",
+            Format::Ply => "ply",
+        }
+    }
+
+    /// Detect the format by the file extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("obj") => Ok(Format::Obj),
+            Some("off") => Ok(Format::Off),
+            Some("stl") => Ok(Format::Stl),
+            Some("ply") => Ok(Format::Ply),
+            _ => Err(format!(
+                "Unsupported mesh format of {}, expected one of: {}",
+                path.display(),
+                Format::EXTENSIONS.join(", ")
+            )),
+        }
+    }
+}
+
+impl<N> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Load the mesh in the format detected by the file extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        match Format::from_path(&path)? {
+            Format::Obj => Self::load_obj(path),
+            Format::Off => Self::load_off(path),
+            Format::Stl => Self::load_stl(path),
+            Format::Ply => Self::load_ply(path),
+        }
+    }
+
+    /// Save the mesh in the format detected by the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        match Format::from_path(&path)? {
+            Format::Obj => self.save_obj(path),
+            Format::Off => self.save_off(path),
+            Format::Stl => self.save_stl(path),
+            Format::Ply => self.save_ply(path),
+        }
+    }
+
+    pub fn load_off(path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = read_text(path.as_ref(), "OFF")?;
+        // comments are allowed anywhere in the file
+        let mut tokens = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+
+        if tokens.next() != Some("OFF") {
+            return Err("Failed to load OFF file: missing OFF header".to_owned());
+        }
+        // counts and indices are non-negative integers
+        let vertices_count: usize = next_off_value(&mut tokens, "vertices count")?;
+        let faces_count: usize = next_off_value(&mut tokens, "faces count")?;
+        let _edges_count: usize = next_off_value(&mut tokens, "edges count")?;
+
+        let mut mesh = Self::default();
+        for _ in 0..vertices_count {
+            let (x, y, z) = (
+                next_off_value(&mut tokens, "vertex")?,
+                next_off_value(&mut tokens, "vertex")?,
+                next_off_value(&mut tokens, "vertex")?,
+            );
+            mesh.vertices.push(vertex_from_f64(x, y, z));
+        }
+        for _ in 0..faces_count {
+            let size: usize = next_off_value(&mut tokens, "face size")?;
+            let face = (0..size)
+                .map(|_| next_off_value(&mut tokens, "face index"))
+                .collect::<Result<_, _>>()?;
+            mesh.faces.push(face);
+        }
+        Ok(mesh)
+    }
+
+    pub fn save_off(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let mut text = String::new();
+        let _ = writeln!(text, "OFF");
+        let _ = writeln!(text, "{} {} 0", self.vertices.len(), self.faces.len());
+        for vertex in &self.vertices {
+            let [x, y, z] = vertex_to_f64(*vertex);
+            let _ = writeln!(text, "{x} {y} {z}");
+        }
+        for face in &self.faces {
+            let _ = write!(text, "{}", face.len());
+            for index in face {
+                let _ = write!(text, " {index}");
+            }
+            let _ = writeln!(text);
+        }
+        write_file(path.as_ref(), text.as_bytes(), "OFF")
+    }
+
+    /// Load the binary or ASCII STL file.
+    /// STL stores each triangle separately, so equal vertices are merged.
+    pub fn load_stl(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to load STL file: {e}"))?;
+
+        // ASCII file starts with `solid`, but some binary files too, so check the binary size
+        let binary_size = bytes.get(80..84).map(|count| {
+            84 + 50 * u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize
+        });
+        let triangles: Vec<[[f32; 3]; 3]> =
+            if bytes.starts_with(b"solid") && binary_size != Some(bytes.len()) {
+                let text = String::from_utf8(bytes)
+                    .map_err(|e| format!("Failed to load STL file: {e}"))?;
+                let coordinates = text
+                    .lines()
+                    .filter_map(|line| line.trim().strip_prefix("vertex"))
+                    .map(|line| {
+                        let coordinates = line
+                            .split_whitespace()
+                            .map(|value| value.parse::<f32>())
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|e| format!("Failed to load STL file: invalid vertex: {e}"))?;
+                        match coordinates.as_slice() {
+                            &[x, y, z] => Ok([x, y, z]),
+                            _ => Err(format!("Failed to load STL file: invalid vertex `{line}`")),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if coordinates.len() % 3 != 0 {
+                    return Err("Failed to load STL file: facet must have 3 vertices".to_owned());
+                }
+                coordinates
+                    .chunks(3)
+                    .map(|chunk| [chunk[0], chunk[1], chunk[2]])
+                    .collect()
+            } else {
+                if binary_size != Some(bytes.len()) {
+                    return Err("Failed to load STL file: unexpected file size".to_owned());
+                }
+                bytes[84..]
+                    .chunks(50)
+                    .map(|triangle| {
+                        let value = |offset: usize| {
+                            let b = &triangle[offset..offset + 4];
+                            f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+                        };
+                        // skip the normal, it's calculated from the vertices
+                        [0, 1, 2].map(|i| [0, 1, 2].map(|j| value(12 + 12 * i + 4 * j)))
+                    })
+                    .collect()
+            };
+
+        let mut mesh = Self::default();
+        let mut indices: AHashMap<[u32; 3], usize> = AHashMap::new();
+        for triangle in triangles {
+            let face = triangle
+                .iter()
+                .map(|&vertex| {
+                    *indices.entry(vertex.map(f32::to_bits)).or_insert_with(|| {
+                        mesh.vertices.push(vertex.into());
+                        mesh.vertices.len() - 1
+                    })
+                })
+                .collect();
+            mesh.faces.push(face);
+        }
+        Ok(mesh)
+    }
+
+    /// Save the binary STL file. Polygons are split into triangle fans.
+    /// Returns an error if a face has invalid vertex indices.
+    pub fn save_stl(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        if let Some(face_index) = self
+            .faces
+            .iter()
+            .position(|face| face.iter().any(|&index| index >= self.vertices.len()))
+        {
+            return Err(format!(
+                "Failed to save STL file: face {face_index} has invalid vertex indices"
+            ));
+        }
+        let triangles: Vec<[usize; 3]> = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len().saturating_sub(1)).map(|i| [face[0], face[i], face[i + 1]])
+            })
+            .collect();
+
+        let mut bytes = Vec::with_capacity(84 + 50 * triangles.len());
+        let mut header = [0u8; 80];
+        let title = b"cantellation";
+        header[..title.len()].copy_from_slice(title);
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|index| self.vertices[index]);
+            let normal = (b - a)
+                .cross(c - a)
+                .normalize(N::zero())
+                .unwrap_or(Vec3::zero());
+            for vertex in [normal, a, b, c] {
+                for value in <[f32; 3]>::from(vertex) {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&0u16.to_le_bytes());
+        }
+        write_file(path.as_ref(), &bytes, "STL")
+    }
+
+    /// Load the ASCII PLY file with `vertex` and `face` elements.
+    pub fn load_ply(path: impl AsRef<Path>) -> Result<Self, String> {
+        let text = read_text(path.as_ref(), "PLY")?;
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some("ply") {
+            return Err("Failed to load PLY file: missing ply header".to_owned());
+        }
+
+        // elements with their count and properties
+        let mut elements: Vec<(String, usize, Vec<String>)> = Vec::new();
+        for line in lines.by_ref() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["end_header"] => break,
+                ["format", format, ..] if *format != "ascii" => {
+                    return Err(format!(
+                        "Failed to load PLY file: unsupported format {format}"
+                    ));
+                }
+                ["element", name, count] => {
+                    let count = count.parse().map_err(|e| {
+                        format!("Failed to load PLY file: invalid element count: {e}")
+                    })?;
+                    elements.push((name.to_string(), count, Vec::new()));
+                }
+                ["property", .., name] => {
+                    if let Some((_, _, properties)) = elements.last_mut() {
+                        properties.push(name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut mesh = Self::default();
+        for (name, count, properties) in &elements {
+            for _ in 0..*count {
+                let line = lines.next().ok_or_else(|| {
+                    format!("Failed to load PLY file: unexpected end of {name} element")
+                })?;
+                let values: Vec<&str> = line.split_whitespace().collect();
+                let parse = |index: usize| ply_value::<f64>(&values, index, line);
+                match name.as_str() {
+                    "vertex" => {
+                        let coordinate = |axis: &str| -> Result<f64, String> {
+                            let index = properties
+                                .iter()
+                                .position(|property| property == axis)
+                                .ok_or_else(|| {
+                                    format!(
+                                        "Failed to load PLY file: missing vertex property {axis}"
+                                    )
+                                })?;
+                            parse(index)
+                        };
+                        mesh.vertices.push(vertex_from_f64(
+                            coordinate("x")?,
+                            coordinate("y")?,
+                            coordinate("z")?,
+                        ));
+                    }
+                    // the list of indices is expected to be the first property of the face
+                    "face" => {
+                        // the size and indices are non-negative integers
+                        let size = ply_value::<usize>(&values, 0, line)?;
+                        let face = (1..=size)
+                            .map(|index| ply_value(&values, index, line))
+                            .collect::<Result<_, _>>()?;
+                        mesh.faces.push(face);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Save the ASCII PLY file.
+    pub fn save_ply(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let mut text = String::new();
+        let _ = writeln!(text, "ply");
+        let _ = writeln!(text, "format ascii 1.0");
+        let _ = writeln!(text, "element vertex {}", self.vertices.len());
+        let _ = writeln!(text, "property double x");
+        let _ = writeln!(text, "property double y");
+        let _ = writeln!(text, "property double z");
+        let _ = writeln!(text, "element face {}", self.faces.len());
+        let _ = writeln!(text, "property list uint uint vertex_indices");
+        let _ = writeln!(text, "end_header");
+        for vertex in &self.vertices {
+            let [x, y, z] = vertex_to_f64(*vertex);
+            let _ = writeln!(text, "{x} {y} {z}");
+        }
+        for face in &self.faces {
+            let _ = write!(text, "{}", face.len());
+            for index in face {
+                let _ = write!(text, " {index}");
+            }
+            let _ = writeln!(text);
+        }
+        write_file(path.as_ref(), text.as_bytes(), "PLY")
+    }
+}
+
+/// Parse the next value of the OFF file, `name` describes the value in the error.
+fn next_off_value<'a, T>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    name: &str,
+) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let token = tokens.next().ok_or_else(|| {
+        format!("Failed to load OFF file: unexpected end of file, expected {name}")
+    })?;
+    token
+        .parse()
+        .map_err(|e| format!("Failed to load OFF file: invalid {name} `{token}`: {e}"))
+}
+
+/// Parse the value of the PLY element line by its index.
+fn ply_value<T>(values: &[&str], index: usize, line: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    values
+        .get(index)
+        .ok_or_else(|| format!("Failed to load PLY file: missing value in `{line}`"))?
+        .parse()
+        .map_err(|e| format!("Failed to load PLY file: invalid value in `{line}`: {e}"))
+}
+
+fn vertex_from_f64<N>(x: f64, y: f64, z: f64) -> Vec3<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    Vec3::new(
+        N::from_f64(x).unwrap_or_default(),
+        N::from_f64(y).unwrap_or_default(),
+        N::from_f64(z).unwrap_or_default(),
+    )
+}
+
+fn vertex_to_f64<N>(vertex: Vec3<N>) -> [f64; 3]
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    [vertex.x, vertex.y, vertex.z].map(|value| value.to_f64().unwrap_or_default())
+}
+
+fn read_text(path: &Path, format: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("Failed to load {format} file: {e}"))
+}
+
+fn write_file(path: &Path, bytes: &[u8], format: &str) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to save {format} file: {e}"))
+}
@@ -0,0 +1,126 @@
+use crate::{
+    cantellate::{cantellate_selected, CantellateParams, Cantellated, FaceKind},
+    mesh::{Mesh, SmallVec},
+    normals::FaceNormals,
+    simplify::compact,
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use ahash::AHashSet;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Lattice of the cantellated mesh: edge strips become struts, vertex caps become nodes
+/// and faces of the original mesh become holes.
+/// The lattice is thickened by the wall thickness, see `thicken`.
+pub fn frame<N>(mesh: &Mesh<N>, params: &CantellateParams<N>, thickness: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let cantellated = cantellate_selected(mesh, params, &vec![true; mesh.faces.len()]);
+    frame_cantellated(&cantellated, thickness, &params.tolerances)
+}
+
+/// Lattice of the result of the selective cantellation.
+/// Only selected faces become holes, unselected faces stay solid and are thickened with the lattice.
+pub fn frame_cantellated<N>(
+    cantellated: &Cantellated<N>,
+    thickness: N,
+    tolerances: &Tolerances<N>,
+) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces = cantellated
+        .mesh
+        .faces
+        .iter()
+        .zip(&cantellated.kinds)
+        .zip(&cantellated.selected)
+        .filter(|&((_, &kind), &selected)| kind != FaceKind::Face || !selected)
+        .map(|((face, _), _)| face.clone())
+        .collect();
+    let open = compact(&cantellated.mesh.vertices, faces);
+    thicken(&open, thickness, tolerances)
+}
+
+/// Turn the surface into a solid shell with the wall thickness.
+///
+/// The inner surface is the copy of the surface moved against vertex normals with reversed faces,
+/// boundary loops of both surfaces are joined by quads, so an open surface becomes closed.
+/// Vertices are moved further at sharp edges, so the distance between planes of outer and inner faces
+/// is close to the thickness, but at most twice the thickness.
+/// Zero thickness keeps the surface as is.
+pub fn thicken<N>(mesh: &Mesh<N>, thickness: N, tolerances: &Tolerances<N>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    if thickness <= N::zero() {
+        return mesh.clone();
+    }
+    let faces_normal = FaceNormals::new(mesh, tolerances).normals;
+
+    // normals of faces around each vertex
+    let mut vertex_normals: Vec<Vec<Vec3<N>>> = vec![Vec::new(); mesh.vertices.len()];
+    for (face, normal) in mesh.faces.iter().zip(&faces_normal) {
+        let Some(normal) = *normal else {
+            continue;
+        };
+        for &index in face {
+            if let Some(normals) = vertex_normals.get_mut(index) {
+                normals.push(normal);
+            }
+        }
+    }
+
+    // inner vertices follow outer vertices, vertices without normals stay in place
+    let half = N::from_f32(0.5).unwrap();
+    let count = mesh.vertices.len();
+    let mut result = Mesh {
+        vertices: mesh.vertices.clone(),
+        faces: Vec::with_capacity(mesh.faces.len() * 2),
+        ..Default::default()
+    };
+    for (vertex, normals) in mesh.vertices.iter().zip(&vertex_normals) {
+        let sum = normals
+            .iter()
+            .fold(Vec3::zero(), |sum, &normal| sum + normal);
+        let inner = match sum.normalize(tolerances.angle) {
+            Some(direction) => {
+                let cos = normals
+                    .iter()
+                    .fold(N::one(), |cos, &normal| cos.min(direction.dot(normal)));
+                *vertex - direction * (thickness / cos.max(half))
+            }
+            None => *vertex,
+        };
+        result.vertices.push(inner);
+    }
+
+    // outer faces, reversed inner faces and quads of boundary edges
+    let faces: Vec<&SmallVec<usize>> = mesh
+        .faces
+        .iter()
+        .filter(|face| face.iter().all(|&index| index < count))
+        .collect();
+    let edges: AHashSet<(usize, usize)> = faces
+        .iter()
+        .flat_map(|face| (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()])))
+        .collect();
+    result.faces.extend(faces.iter().map(|&face| face.clone()));
+    result.faces.extend(
+        faces
+            .iter()
+            .map(|face| face.iter().rev().map(|&index| index + count).collect()),
+    );
+    for face in &faces {
+        for i in 0..face.len() {
+            let (a, b) = (face[i], face[(i + 1) % face.len()]);
+            if !edges.contains(&(b, a)) {
+                result
+                    .faces
+                    .push(SmallVec::from_slice(&[b, a, a + count, b + count]));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,312 @@
+use crate::{
+    cantellate::{cantellate, get_faces_normal, CantellateParams},
+    mesh::{Mesh, SmallVec},
+    simplify::{compact, merge_coplanar_faces},
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use ahash::AHashMap;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Convex hull of the points by the quickhull algorithm.
+///
+/// Points closer to the hull than the planarity tolerance are considered inside,
+/// coplanar triangles of the hull are merged into polygons, see `merge_coplanar_faces`.
+/// The merge angle is the planarity tolerance divided by the size of the hull,
+/// so merged polygons deviate from their planes by about the planarity tolerance.
+/// Faces are oriented outwards, the result contains only vertices of the hull.
+/// Returns an error if all points are coplanar.
+pub fn convex_hull<N>(points: &[Vec3<N>], tolerances: &Tolerances<N>) -> Result<Mesh<N>, String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let tolerance = tolerances.planarity;
+    let simplex = initial_simplex(points, tolerance).ok_or_else(|| {
+        "Convex hull requires at least 4 points which are not coplanar".to_owned()
+    })?;
+
+    let mut faces: Vec<HullFace<N>> = Vec::new();
+    let mut edges: AHashMap<(usize, usize), usize> = AHashMap::new();
+    let [a, b, c, d] = simplex;
+    for vertices in [[a, b, c], [a, d, b], [b, d, c], [c, d, a]] {
+        add_face(points, vertices, &mut faces, &mut edges);
+    }
+    let new_faces: Vec<usize> = (0..faces.len()).collect();
+    let others = (0..points.len()).filter(|index| !simplex.contains(index));
+    assign_points(points, others, &new_faces, &mut faces, tolerance);
+
+    // add the farthest outside point of a face until there are no outside points
+    while let Some(start) = faces
+        .iter()
+        .position(|face| face.alive && !face.outside.is_empty())
+    {
+        let eye = faces[start]
+            .outside
+            .iter()
+            .copied()
+            .max_by(|&i, &j| {
+                let (a, b) = (
+                    faces[start].distance(points[i]),
+                    faces[start].distance(points[j]),
+                );
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap();
+
+        // faces visible from the eye are found by the flood fill, the horizon is their border with other faces,
+        // the eye is compared with planes without the tolerance, so the new faces don't make concave edges
+        let mut is_visible: AHashMap<usize, bool> = AHashMap::new();
+        is_visible.insert(start, true);
+        let mut stack = vec![start];
+        let mut visible = Vec::new();
+        let mut horizon = Vec::new();
+        while let Some(face_index) = stack.pop() {
+            visible.push(face_index);
+            let [a, b, c] = faces[face_index].vertices;
+            for edge in [(a, b), (b, c), (c, a)] {
+                let Some(&neighbour) = edges.get(&(edge.1, edge.0)) else {
+                    continue;
+                };
+                let neighbour_visible = *is_visible.entry(neighbour).or_insert_with(|| {
+                    let visible = faces[neighbour].distance(points[eye]) > N::zero();
+                    if visible {
+                        stack.push(neighbour);
+                    }
+                    visible
+                });
+                if !neighbour_visible {
+                    horizon.push(edge);
+                }
+            }
+        }
+
+        // replace visible faces by the cone from the horizon to the eye
+        let mut orphans = Vec::new();
+        for &face_index in &visible {
+            let face = &mut faces[face_index];
+            face.alive = false;
+            orphans.append(&mut face.outside);
+            let [a, b, c] = face.vertices;
+            for edge in [(a, b), (b, c), (c, a)] {
+                edges.remove(&edge);
+            }
+        }
+        let first = faces.len();
+        for (a, b) in horizon {
+            add_face(points, [a, b, eye], &mut faces, &mut edges);
+        }
+        let new_faces: Vec<usize> = (first..faces.len()).collect();
+        let orphans = orphans.into_iter().filter(|&index| index != eye);
+        assign_points(points, orphans, &new_faces, &mut faces, tolerance);
+    }
+
+    let triangles = faces
+        .iter()
+        .filter(|face| face.alive)
+        .map(|face| SmallVec::from_slice(&face.vertices))
+        .collect();
+    let hull = compact(points, triangles);
+    let size = hull
+        .vertices
+        .iter()
+        .map(|&vertex| (vertex - hull.vertices[0]).length())
+        .fold(N::zero(), N::max);
+    let angle = (tolerances.planarity / size).min(tolerances.angle);
+    Ok(merge_coplanar_faces(&hull, angle, tolerances))
+}
+
+/// The mesh is convex if each face lies in the plane of a face of its convex hull
+/// and has the same orientation, so each face is a part of the hull surface.
+/// Faces must be planar by the planarity tolerance. The mesh is not checked to be closed.
+/// Meshes without a hull, e.g. flat meshes, and meshes with invalid indices are not convex.
+pub fn is_convex<N>(mesh: &Mesh<N>, tolerances: &Tolerances<N>) -> bool
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    if mesh
+        .faces
+        .iter()
+        .flatten()
+        .any(|&index| index >= mesh.vertices.len())
+    {
+        return false;
+    }
+    let mut used = vec![false; mesh.vertices.len()];
+    mesh.faces
+        .iter()
+        .flatten()
+        .for_each(|&index| used[index] = true);
+    let points: Vec<Vec3<N>> = mesh
+        .vertices
+        .iter()
+        .zip(&used)
+        .filter(|(_, &used)| used)
+        .map(|(&vertex, _)| vertex)
+        .collect();
+    let Ok(hull) = convex_hull(&points, tolerances) else {
+        return false;
+    };
+
+    // planes of hull faces
+    let planes: Vec<(Vec3<N>, N)> = get_faces_normal(&hull, N::zero())
+        .iter()
+        .zip(&hull.faces)
+        .filter_map(|(normal, face)| {
+            let normal = (*normal)?;
+            Some((normal, normal.dot(hull.vertices[face[0]])))
+        })
+        .collect();
+
+    // each face lies in the plane of a hull face, degenerate faces have no orientation to compare
+    let on_plane = |face: &[usize], &(normal, offset): &(Vec3<N>, N)| {
+        face.iter()
+            .all(|&index| (normal.dot(mesh.vertices[index]) - offset).abs() <= tolerances.planarity)
+    };
+    let faces_normal = get_faces_normal(mesh, tolerances.face_normal());
+    mesh.faces
+        .iter()
+        .zip(&faces_normal)
+        .all(|(face, normal)| match normal {
+            Some(normal) => planes
+                .iter()
+                .any(|plane| plane.0.dot(*normal) > N::zero() && on_plane(face, plane)),
+            None => face.iter().all(|&index| {
+                planes
+                    .iter()
+                    .any(|plane| on_plane(std::slice::from_ref(&index), plane))
+            }),
+        })
+}
+
+/// Cantellation of the convex mesh as the convex hull of the cantellated vertices.
+/// For convex meshes it's the expansion polytope with coplanar faces merged,
+/// other meshes are replaced by the hull of their cantellation.
+pub fn cantellate_hull<N>(mesh: &Mesh<N>, params: &CantellateParams<N>) -> Result<Mesh<N>, String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    convex_hull(&cantellate(mesh, params).vertices, &params.tolerances)
+}
+
+/// Triangle of the hull in progress with points above its plane.
+struct HullFace<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    vertices: [usize; 3],
+    normal: Vec3<N>,
+    offset: N,
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl<N> HullFace<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Signed distance from the plane, positive outside.
+    /// Triangles with collinear vertices have a zero normal, so nothing is outside of them.
+    fn distance(&self, point: Vec3<N>) -> N {
+        self.normal.dot(point) - self.offset
+    }
+}
+
+fn add_face<N>(
+    points: &[Vec3<N>],
+    vertices: [usize; 3],
+    faces: &mut Vec<HullFace<N>>,
+    edges: &mut AHashMap<(usize, usize), usize>,
+) where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let [a, b, c] = vertices.map(|index| points[index]);
+    let normal = (b - a)
+        .cross(c - a)
+        .normalize(N::zero())
+        .unwrap_or_else(Vec3::zero);
+    let [i, j, k] = vertices;
+    for edge in [(i, j), (j, k), (k, i)] {
+        edges.insert(edge, faces.len());
+    }
+    faces.push(HullFace {
+        vertices,
+        normal,
+        offset: normal.dot(a),
+        outside: Vec::new(),
+        alive: true,
+    });
+}
+
+/// Add each point to the outside set of the first face it's above, other points are inside the hull.
+fn assign_points<N>(
+    points: &[Vec3<N>],
+    indices: impl Iterator<Item = usize>,
+    candidates: &[usize],
+    faces: &mut [HullFace<N>],
+    tolerance: N,
+) where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    for index in indices {
+        let face = candidates
+            .iter()
+            .find(|&&face_index| faces[face_index].distance(points[index]) > tolerance);
+        if let Some(&face_index) = face {
+            faces[face_index].outside.push(index);
+        }
+    }
+}
+
+/// Tetrahedron of extreme points, the first triangle faces away from the last point.
+fn initial_simplex<N>(points: &[Vec3<N>], tolerance: N) -> Option<[usize; 4]>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let farthest = |distance: &dyn Fn(Vec3<N>) -> N| {
+        (0..points.len()).max_by(|&i, &j| {
+            distance(points[i])
+                .partial_cmp(&distance(points[j]))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+    };
+
+    // the farthest pair of extreme points along axes
+    let axes: [fn(&Vec3<N>) -> N; 3] = [|v| v.x, |v| v.y, |v| v.z];
+    let extremes: Vec<usize> = axes
+        .iter()
+        .flat_map(|axis| [farthest(&|v| axis(&v)), farthest(&|v| -axis(&v))])
+        .collect::<Option<_>>()?;
+    let (a, b) = extremes
+        .iter()
+        .flat_map(|&i| extremes.iter().map(move |&j| (i, j)))
+        .max_by(|&(i, j), &(k, l)| {
+            (points[i] - points[j])
+                .length()
+                .partial_cmp(&(points[k] - points[l]).length())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+    if (points[a] - points[b]).length() <= tolerance {
+        return None;
+    }
+
+    // the farthest point from the line and from the plane
+    let direction = (points[b] - points[a]) / (points[b] - points[a]).length();
+    let line_distance = |v: Vec3<N>| (v - points[a]).cross(direction).length();
+    let c = farthest(&line_distance)?;
+    if line_distance(points[c]) <= tolerance {
+        return None;
+    }
+    let normal = (points[b] - points[a]).cross(points[c] - points[a]);
+    let normal = normal / normal.length();
+    let plane_distance = |v: Vec3<N>| (v - points[a]).dot(normal);
+    let d = farthest(&|v| plane_distance(v).abs())?;
+    let distance = plane_distance(points[d]);
+    if distance.abs() <= tolerance {
+        return None;
+    }
+    Some(if distance > N::zero() {
+        [a, c, b, d]
+    } else {
+        [a, b, c, d]
+    })
+}
@@ -0,0 +1,17 @@
+pub mod cantellate;
+mod disjoint_set;
+pub mod formats;
+pub mod frame;
+pub mod hull;
+pub mod mesh;
+pub mod normals;
+pub mod primitives;
+pub mod selection;
+pub mod simplify;
+pub mod stats;
+pub mod subdivide;
+pub mod tolerance;
+pub mod triangulate;
+pub mod uncantellate;
+pub mod validate;
+pub mod vec3;
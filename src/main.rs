@@ -1,43 +1,550 @@
-mod cantellate;
-mod mesh;
-mod vec3;
+mod config;
 
-use std::path::PathBuf;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
-use cantellate::cantellate;
-use clap::Parser;
-use mesh::Mesh;
+use cantellation::{
+    cantellate::{cantellate_selected, CantellateParams, Placement},
+    formats::Format,
+    frame::frame_cantellated,
+    hull::{cantellate_hull, is_convex},
+    mesh::{Mesh, DEFAULT_GROUP},
+    primitives::Primitive,
+    selection::Selection,
+    simplify::{merge_coplanar_faces, weld_vertices_faces},
+    stats::{MeshStats, StatsReport},
+    subdivide::{subdivide, Scheme},
+    tolerance::Tolerances,
+    triangulate::{split_faces, OutputFaces},
+    uncantellate::uncantellate,
+    validate::{repair, repair_faces, ValidationReport},
+    vec3::Vec3,
+};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use config::RunConfig;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use num_traits::{Float, FromPrimitive, ToPrimitive};
 
 /// Command line arguments.
 #[derive(Parser, Debug, Clone)]
 #[clap(about)]
-pub struct Args {
-    /// Input `.obj` file.
-    /// If input is a directory, all `.obj` files in the directory will be processed.
-    #[arg(short, long)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[command(flatten)]
+    common: CommonOptions,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Cantellate the mesh and save the result.
+    Cantellate(CantellateArgs),
+
+    /// Convert the mesh to another file format.
+    Convert(ConvertArgs),
+
+    /// Print statistics of the mesh before and after the cantellation.
+    Stats(StatsArgs),
+
+    /// Check the mesh for topology problems and optionally save the repaired mesh.
+    Validate(ValidateArgs),
+
+    /// Generate the built-in primitive and save it.
+    Generate(GenerateArgs),
+}
+
+/// Options shared by all commands.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CommonOptions {
+    /// Epsilon value for floating point comparison.
+    #[clap(short, long, global = true, default_value_t = 0.001)]
+    epsilon: f32,
+
+    /// Minimal length of a vector to have a direction, e.g. for face normals.
+    /// Equal to the epsilon by default.
+    #[clap(long, global = true, value_name = "TOL")]
+    normal_tolerance: Option<f32>,
+
+    /// Maximal distance between vertices which are considered the same.
+    /// Equal to the epsilon by default.
+    #[clap(long, global = true, value_name = "TOL")]
+    merge_tolerance: Option<f32>,
+
+    /// Maximal distance of a vertex from the face plane for the face to be planar.
+    /// Equal to the epsilon by default.
+    #[clap(long, global = true, value_name = "TOL")]
+    planarity_tolerance: Option<f32>,
+
+    /// Minimal angle in radians between directions which are considered different.
+    /// Equal to the epsilon by default.
+    #[clap(long, global = true, value_name = "TOL")]
+    angle_tolerance: Option<f32>,
+
+    /// Minimal distance of a vertex from the face plane for the face to be pushed along the normal of the best-fit plane.
+    /// Ten times the planarity tolerance by default.
+    #[clap(long, global = true, value_name = "TOL")]
+    fit_plane_tolerance: Option<f32>,
+
+    /// Multiply length tolerances by the diagonal of the input mesh bounding box, the angle tolerance is kept.
+    #[clap(long, global = true)]
+    relative_tolerances: bool,
+
+    /// Precision of the calculations.
+    #[clap(long, global = true, value_enum, default_value_t = Precision::F64)]
+    precision: Precision,
+}
+
+impl CommonOptions {
+    /// Tolerances for the input mesh.
+    fn tolerances<N>(&self, mesh: &Mesh<N>) -> Tolerances<N>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let value =
+            |tolerance: Option<f32>| N::from_f32(tolerance.unwrap_or(self.epsilon)).unwrap();
+        let tolerances = Tolerances {
+            normal: value(self.normal_tolerance),
+            merge: value(self.merge_tolerance),
+            planarity: value(self.planarity_tolerance),
+            angle: value(self.angle_tolerance),
+            fit_plane: match self.fit_plane_tolerance {
+                Some(tolerance) => N::from_f32(tolerance).unwrap(),
+                None => {
+                    value(self.planarity_tolerance)
+                        * N::from_f64(Tolerances::<N>::FIT_PLANE_RATIO).unwrap()
+                }
+            },
+            scale: N::one(),
+        };
+        if self.relative_tolerances {
+            tolerances.relative_to(mesh)
+        } else {
+            tolerances
+        }
+    }
+}
+
+/// Number type used for the calculations.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Single precision floating point.
+    F32,
+    /// Double precision floating point.
+    F64,
+}
+
+/// Arguments of the cantellation.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CantellateArgs {
+    /// Input mesh file, supported formats: `.obj`, `.off`, `.stl`, `.ply`.
+    /// If input is a directory, all supported files in the directory will be processed.
+    /// Built-in primitive is generated by the spec like `gen:icosahedron` or `gen:prism:sides=8,height=2`.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "config",
+        default_value = "",
+        hide_default_value = true
+    )]
     input: String,
 
-    /// Output `.obj` file.
+    /// Output mesh file, the format is detected by the extension.
     /// If input is a directory, all output files will be saved in this directory.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "config",
+        default_value = "",
+        hide_default_value = true
+    )]
+    output: String,
+
+    /// TOML or JSON file with the run description, options of the command line override values of the file.
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Built-in preset of options, values of the config file and the command line override it.
+    #[clap(long, value_parser = clap::builder::PossibleValuesParser::new(RunConfig::PRESETS))]
+    preset: Option<String>,
+
+    /// Format of output files, replaces the extension of the output.
+    #[clap(long, value_enum)]
+    format: Option<Format>,
+
+    /// Kind of faces of output files, polygons are split by the ear clipping.
+    #[clap(long, value_enum, default_value_t = OutputFaces::Ngon)]
+    output_faces: OutputFaces,
+
+    /// Repair the input mesh before the cantellation, see the `validate` command.
+    #[clap(long)]
+    repair: bool,
+
+    /// Process subdirectories of the input directory, output files mirror the input tree.
+    #[clap(short, long)]
+    recursive: bool,
+
+    /// Process only files whose path relative to the input directory matches one of glob patterns.
+    #[clap(long)]
+    include: Vec<String>,
+
+    /// Skip files whose path relative to the input directory matches one of glob patterns.
+    #[clap(long)]
+    exclude: Vec<String>,
+
+    /// Template of output file names in the directory mode.
+    /// Placeholders: `{stem}`, `{ext}`, `{count}`, `{factor}`, `{segments}`, `{iter}`.
+    #[clap(long, default_value = "{stem}.{ext}")]
+    output_name: String,
+
+    /// Recover the original mesh from the cantellated input instead of cantellation.
+    /// Each iteration collapses one cantellation.
+    #[clap(short, long)]
+    uncantellate: bool,
+
+    /// What to do with output files which already exist.
+    /// The input directory stops with an error by default, a single output file is overwritten.
+    #[clap(long, value_enum)]
+    on_existing: Option<OnExisting>,
+
+    /// Print files which would be written without processing them.
+    #[clap(short = 'n', long)]
+    dry_run: bool,
+
+    /// Count of files processed in parallel in the directory mode, 0 uses all cores.
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Save each N-th iteration, the output path must contain the `{iter}` placeholder.
+    /// All iterations are saved by default if the placeholder is used.
+    #[clap(long)]
+    save_every: Option<usize>,
+
+    #[command(flatten)]
+    options: CantellateOptions,
+}
+
+/// Policy for output files which already exist.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnExisting {
+    /// Stop before writing anything.
+    Error,
+    /// Overwrite output files, other files in the output directory are kept.
+    OverwriteFiles,
+    /// Remove the output directory before processing the input directory.
+    Clean,
+    /// Skip inputs whose output file already exists.
+    Skip,
+}
+
+/// Arguments of the format conversion.
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConvertArgs {
+    /// Input mesh file or built-in primitive spec like `gen:icosahedron`.
+    #[arg(short, long)]
+    input: String,
+
+    /// Output mesh file, the format is detected by the extension.
+    #[arg(short, long)]
+    output: String,
+
+    /// Kind of faces of the output mesh.
+    #[clap(long, value_enum, default_value_t = OutputFaces::Ngon)]
+    output_faces: OutputFaces,
+}
+
+/// Arguments of the statistics.
+#[derive(clap::Args, Debug, Clone)]
+pub struct StatsArgs {
+    /// Input mesh file or built-in primitive spec like `gen:icosahedron`.
+    #[arg(short, long)]
+    input: String,
+
+    /// Print the report in JSON format.
+    #[clap(short, long)]
+    json: bool,
+
+    #[command(flatten)]
+    options: CantellateOptions,
+}
+
+/// Arguments of the validation.
+#[derive(clap::Args, Debug, Clone)]
+pub struct ValidateArgs {
+    /// Input mesh file.
+    #[arg(short, long)]
+    input: String,
+
+    /// Save the repaired mesh to this file.
+    /// Invalid, degenerate and duplicate faces and unused vertices are removed.
+    #[arg(short, long)]
+    repair: Option<String>,
+
+    /// Print the report in JSON format.
+    #[clap(short, long)]
+    json: bool,
+}
+
+/// Arguments of the primitive generation.
+#[derive(clap::Args, Debug, Clone)]
+pub struct GenerateArgs {
+    /// Primitive spec like `icosahedron` or `prism:sides=8,height=2`.
+    spec: String,
+
+    /// Output mesh file, the format is detected by the extension.
     #[arg(short, long)]
     output: String,
+}
 
+/// Options of the cantellation algorithm.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CantellateOptions {
     /// Cantellation factor.
     #[clap(short, long, default_value_t = 1.0)]
     factor: f32,
 
-    /// Epsilon value for floating point comparison.
-    #[clap(short, long, default_value_t = 0.001)]
-    epsilon: f32,
+    /// Placement of the cantellated vertices.
+    #[clap(short, long, value_enum, default_value_t = Placement::Normal)]
+    placement: Placement,
+
+    /// In-plane inset distance for the `inset-normal` placement.
+    /// Equal to the cantellation factor by default.
+    #[clap(long)]
+    inset: Option<f32>,
+
+    /// Count of faces in each new edge strip.
+    /// Values greater than 1 round edges and vertices of the cantellated mesh, only with the normal placement.
+    #[clap(short, long, default_value_t = 1)]
+    segments: usize,
+
+    /// Cantellate only sharp edges whose face normals differ by at least the angle in degrees.
+    /// Smoother edges stay connected and their vertices are not split.
+    #[clap(long, value_name = "DEGREES", default_value_t = 0.0)]
+    min_angle: f32,
 
     /// Count of cantellation iterations.
     #[clap(short, long, default_value_t = 1)]
     count: usize,
 
-    /// Use double precision.
-    #[clap(short, long, default_value_t = true)]
-    double: bool,
+    /// Merge adjacent faces of each iteration result whose normals differ by at most the angle in degrees.
+    /// Zero-width edge strips and collinear vertices are removed.
+    #[clap(long, value_name = "DEGREES")]
+    merge_coplanar: Option<f32>,
+
+    /// Weld vertices closer than the distance in the input mesh and after each iteration.
+    #[clap(long, value_name = "DISTANCE")]
+    weld: Option<f32>,
+
+    /// Count of subdivision steps applied to the result of all iterations.
+    #[clap(long, value_name = "COUNT", default_value_t = 0)]
+    subdivide: usize,
+
+    /// Subdivision scheme of `--subdivide`.
+    #[clap(long, value_enum, default_value_t = Scheme::CatmullClark)]
+    subdivision: Scheme,
+
+    /// Turn the last iteration into a lattice with the wall thickness:
+    /// faces become holes, edge strips and vertex caps are thickened inwards.
+    #[clap(long, value_name = "THICKNESS")]
+    frame: Option<f32>,
+
+    /// Build each iteration as the convex hull of the cantellated vertices with merged coplanar faces.
+    /// It's the exact expansion of convex meshes, non-convex meshes are replaced by the hull.
+    #[clap(long)]
+    hull: bool,
+
+    /// Cantellate only faces with these indices, separated by commas.
+    #[clap(long, value_name = "INDICES", value_delimiter = ',')]
+    select_faces: Vec<usize>,
+
+    /// Cantellate only faces of the OBJ group (`g` statement).
+    #[clap(long, value_name = "NAME")]
+    select_group: Option<String>,
+
+    /// Cantellate only faces whose centroid is inside the box `min_x,min_y,min_z,max_x,max_y,max_z`.
+    #[clap(
+        long,
+        value_name = "BOX",
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    select_box: Vec<f32>,
+
+    /// Cantellate only faces whose normal deviates from the direction by at most the angle, `x,y,z,degrees`.
+    #[clap(
+        long,
+        value_name = "CONE",
+        value_delimiter = ',',
+        allow_hyphen_values = true
+    )]
+    select_normal: Vec<f32>,
+}
+
+impl CantellateOptions {
+    fn params<N>(&self, tolerances: Tolerances<N>) -> Result<CantellateParams<N>, String>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let params = CantellateParams {
+            factor: N::from_f32(self.factor).unwrap(),
+            tolerances,
+            placement: self.placement,
+            inset: N::from_f32(self.inset.unwrap_or(self.factor)).unwrap(),
+            segments: self.segments,
+            min_angle: N::from_f32(self.min_angle.to_radians()).unwrap(),
+        };
+        params.check()?;
+        Ok(params)
+    }
+
+    /// Selections of faces by the options.
+    fn selections<N>(&self) -> Result<Vec<Selection<N>>, String>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let vec3 = |v: &[f32]| Vec3::from([v[0], v[1], v[2]]);
+        let mut selections = Vec::new();
+        if !self.select_faces.is_empty() {
+            selections.push(Selection::Faces(self.select_faces.clone()));
+        }
+        if let Some(name) = &self.select_group {
+            selections.push(Selection::Group(name.clone()));
+        }
+        if !self.select_box.is_empty() {
+            if self.select_box.len() != 6 {
+                return Err(
+                    "Selection box requires 6 values: min_x,min_y,min_z,max_x,max_y,max_z"
+                        .to_owned(),
+                );
+            }
+            selections.push(Selection::BoundingBox {
+                min: vec3(&self.select_box[..3]),
+                max: vec3(&self.select_box[3..]),
+            });
+        }
+        if !self.select_normal.is_empty() {
+            if self.select_normal.len() != 4 {
+                return Err("Selection normal cone requires 4 values: x,y,z,degrees".to_owned());
+            }
+            selections.push(Selection::NormalCone {
+                direction: vec3(&self.select_normal[..3]),
+                angle: N::from_f32(self.select_normal[3].to_radians()).unwrap(),
+            });
+        }
+        if !selections.is_empty() && self.hull {
+            return Err("Selection can't be combined with --hull".to_owned());
+        }
+        if !selections.is_empty() && self.merge_coplanar.is_some() && self.count > 1 {
+            return Err(
+                "Selection can't be combined with --merge-coplanar for several iterations"
+                    .to_owned(),
+            );
+        }
+        Ok(selections)
+    }
+
+    /// Faces of the input mesh selected by the options, `None` if there are no selections.
+    /// Group names are read from the input OBJ file.
+    fn selected<N>(
+        &self,
+        input: &str,
+        mesh: &Mesh<N>,
+        epsilon: N,
+    ) -> Result<Option<Vec<bool>>, String>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let selections = self.selections::<N>()?;
+        if selections.is_empty() {
+            return Ok(None);
+        }
+        let face_groups = match Format::from_path(input) {
+            Ok(Format::Obj) if self.select_group.is_some() => Mesh::<N>::load_obj_groups(input)?.1,
+            _ => Vec::new(),
+        };
+        Selection::select_all(&selections, mesh, &face_groups, epsilon).map(Some)
+    }
+
+    /// Single iteration of the cantellation with the post-processing.
+    /// The selection is updated to the selected faces of the result for the next iteration.
+    /// The last iteration is turned into the lattice if requested.
+    fn cantellate<N>(
+        &self,
+        mesh: &Mesh<N>,
+        params: &CantellateParams<N>,
+        selected: &mut Option<Vec<bool>>,
+        is_last: bool,
+    ) -> Result<Mesh<N>, String>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        if self.hull {
+            if !is_convex(mesh, &params.tolerances) {
+                log::warn!("The mesh is not convex, the hull cantellation replaces it with the convex hull");
+            }
+            let result = cantellate_hull(mesh, params)?;
+            return Ok(self.weld(result, selected));
+        }
+        let all_selected;
+        let mask = match selected {
+            Some(selected) => selected.as_slice(),
+            None => {
+                all_selected = vec![true; mesh.faces.len()];
+                &all_selected
+            }
+        };
+        let result = cantellate_selected(mesh, params, mask);
+        if !result.normals.is_empty() {
+            log::warn!("Faces with fallback normals:\n{}", result.normals);
+        }
+        let result = match self.frame {
+            Some(thickness) if is_last => {
+                let thickness = N::from_f32(thickness).unwrap();
+                let framed = frame_cantellated(&result, thickness, &params.tolerances);
+                // faces of the lattice have no selection, there are no more iterations
+                *selected = None;
+                framed
+            }
+            _ => {
+                if let Some(selected) = selected {
+                    *selected = result.selected;
+                }
+                result.mesh
+            }
+        };
+        let result = match self.merge_coplanar {
+            Some(angle) => {
+                let angle = N::from_f32(angle.to_radians()).unwrap();
+                merge_coplanar_faces(&result, angle, &params.tolerances)
+            }
+            None => result,
+        };
+        Ok(self.weld(result, selected))
+    }
+
+    /// Weld close vertices if requested, the selection is updated to the kept faces.
+    fn weld<N>(&self, mesh: Mesh<N>, selected: &mut Option<Vec<bool>>) -> Mesh<N>
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let Some(tolerance) = self.weld else {
+            return mesh;
+        };
+        let (welded, kept) = weld_vertices_faces(&mesh, N::from_f32(tolerance).unwrap());
+        if let Some(selected) = selected {
+            *selected = kept
+                .iter()
+                .map(|&face_index| selected[face_index])
+                .collect();
+        }
+        welded
+    }
 }
 
 fn main() {
@@ -46,81 +553,676 @@ fn main() {
         .init();
 
     // parse command line arguments
-    let args = Args::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let (Command::Cantellate(args), Some(("cantellate", matches))) =
+        (&mut cli.command, matches.subcommand())
+    {
+        let config = match &args.config {
+            Some(path) => RunConfig::load(path),
+            None => Ok(RunConfig::default()),
+        };
+        config
+            .and_then(|config| config.with_preset(args.preset.as_deref()))
+            .and_then(|config| config.apply(args, &mut cli.common, matches))
+            .unwrap_or_else(|e| exit_with_error(&e));
+    }
 
-    let input_path: PathBuf = args.input.clone().into();
-    if input_path.is_file() {
-        if args.double {
-            run::<f64>(&args);
-        } else {
-            run::<f32>(&args);
+    match cli.common.precision {
+        Precision::F32 => run_command::<f32>(&cli),
+        Precision::F64 => run_command::<f64>(&cli),
+    }
+}
+
+fn run_command<N>(cli: &Cli)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    match &cli.command {
+        Command::Cantellate(args) => run_cantellate::<N>(args, &cli.common),
+        Command::Convert(args) => {
+            let (mesh, groups) =
+                load_mesh_groups::<N>(&args.input).unwrap_or_else(|e| exit_with_error(&e));
+            let tolerances = cli.common.tolerances(&mesh);
+            let (mesh, sources) = split_faces(&mesh, args.output_faces, &tolerances);
+            // split faces keep groups of their source faces
+            let groups: Vec<String> = sources
+                .iter()
+                .filter_map(|&face_index| groups.get(face_index).cloned())
+                .collect();
+            save_mesh(&mesh, &groups, &args.output).unwrap_or_else(|e| exit_with_error(&e));
         }
-    } else if input_path.is_dir() {
-        let output_dir = PathBuf::from(&args.output);
-        if output_dir.exists() {
-            std::fs::remove_dir_all(&args.output).unwrap();
+        Command::Stats(args) => run_stats::<N>(args, &cli.common),
+        Command::Validate(args) => run_validate::<N>(args),
+        Command::Generate(args) => {
+            let spec = args.spec.strip_prefix("gen:").unwrap_or(&args.spec);
+            let primitive = spec
+                .parse::<Primitive>()
+                .unwrap_or_else(|e| exit_with_error(&e));
+            let mesh = Mesh::<N>::generate(&primitive);
+            save_mesh(&mesh, &[], &args.output).unwrap_or_else(|e| exit_with_error(&e));
         }
+    }
+}
 
-        // if input is a directory, iterate over all files in the directory
-        for file in input_path.read_dir().unwrap() {
-            let file = file.unwrap();
-            if file.path().is_file()
-                && file.path().extension().and_then(|e| e.to_str()) == Some("obj")
-            {
-                let input = file.path().to_str().unwrap().to_string();
-                let output = output_dir
-                    .join(file.file_name())
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let args = Args {
-                    input,
-                    output,
+fn run_cantellate<N>(args: &CantellateArgs, common: &CommonOptions)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let jobs = cantellate_jobs(args)
+        .and_then(|jobs| apply_on_existing(args, jobs))
+        .unwrap_or_else(|e| exit_with_error(&e));
+    if args.dry_run {
+        return;
+    }
+
+    if !PathBuf::from(&args.input).is_dir() {
+        for (input, output) in jobs {
+            let args = CantellateArgs {
+                input,
+                output,
+                ..args.clone()
+            };
+            if let Err(e) = run::<N>(&args, common) {
+                exit_with_error(&e);
+            }
+        }
+        return;
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs)
+        .build()
+        .unwrap();
+    let done = AtomicUsize::new(0);
+    let reports: Vec<JobReport> = pool.install(|| {
+        jobs.par_iter()
+            .map(|(input, output)| {
+                let job_args = CantellateArgs {
+                    input: input.clone(),
+                    output: output.clone(),
                     ..args.clone()
                 };
-                if args.double {
-                    run::<f64>(&args);
+                let timer = std::time::Instant::now();
+                // a failed file must not stop the others, including panics in the algorithm
+                let result =
+                    std::panic::catch_unwind(AssertUnwindSafe(|| run::<N>(&job_args, common)))
+                        .unwrap_or_else(|_| Err("Panic during processing".to_owned()));
+                let report = JobReport {
+                    input: input.clone(),
+                    time: timer.elapsed(),
+                    result,
+                };
+
+                let done = done.fetch_add(1, Ordering::SeqCst) + 1;
+                match &report.result {
+                    Ok(_) => {
+                        log::info!("[{done}/{}] {input} done in {:?}", jobs.len(), report.time)
+                    }
+                    Err(e) => log::error!("[{done}/{}] {input} failed: {e}", jobs.len()),
+                }
+                report
+            })
+            .collect()
+    });
+
+    print_summary(&reports);
+    if reports.iter().any(|report| report.result.is_err()) {
+        std::process::exit(1);
+    }
+}
+
+/// Log the error and exit with a non-zero code.
+fn exit_with_error(message: &str) -> ! {
+    log::error!("{message}");
+    std::process::exit(1)
+}
+
+/// Result of processing one input file.
+struct JobReport {
+    input: String,
+    time: Duration,
+    /// Faces count of the input and of the output mesh.
+    result: Result<(usize, usize), String>,
+}
+
+/// Print the table of processed files.
+fn print_summary(reports: &[JobReport]) {
+    let rows: Vec<[String; 5]> = reports
+        .iter()
+        .map(|report| {
+            let (input_faces, output_faces, status) = match &report.result {
+                Ok((input_faces, output_faces)) => (
+                    input_faces.to_string(),
+                    output_faces.to_string(),
+                    "ok".to_owned(),
+                ),
+                Err(e) => ("-".to_owned(), "-".to_owned(), format!("error: {e}")),
+            };
+            [
+                report.input.clone(),
+                input_faces,
+                output_faces,
+                format!("{:.3?}", report.time),
+                status,
+            ]
+        })
+        .collect();
+
+    let header = ["file", "input faces", "output faces", "time", "status"].map(str::to_owned);
+    let mut widths = header.clone().map(|title| title.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<_> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+
+    let failed = reports
+        .iter()
+        .filter(|report| report.result.is_err())
+        .count();
+    println!(
+        "{} files processed, {} failed",
+        reports.len() - failed,
+        failed
+    );
+}
+
+/// Pairs of input and output files to process.
+fn cantellate_jobs(args: &CantellateArgs) -> Result<Vec<(String, String)>, String> {
+    let input_path: PathBuf = args.input.clone().into();
+    if input_path.is_file() || args.input.starts_with("gen:") {
+        let output = match args.format {
+            Some(format) => PathBuf::from(&args.output)
+                .with_extension(format.extension())
+                .to_string_lossy()
+                .into_owned(),
+            None => args.output.clone(),
+        };
+        Ok(vec![(args.input.clone(), output)])
+    } else if input_path.is_dir() {
+        let include = glob_set(&args.include)?;
+        let exclude = glob_set(&args.exclude)?;
+
+        // if input is a directory, iterate over all supported files in the directory
+        let mut files = Vec::new();
+        collect_files(&input_path, args.recursive, &mut files)?;
+        files.sort();
+
+        let mut jobs = Vec::new();
+        for file in files {
+            let relative = file.strip_prefix(&input_path).unwrap_or(&file);
+            let matches = |set: &Option<GlobSet>| set.as_ref().map(|set| set.is_match(relative));
+            if Format::from_path(&file).is_err()
+                || matches(&include) == Some(false)
+                || matches(&exclude) == Some(true)
+            {
+                continue;
+            }
+
+            // mirror the relative path of the input file
+            let name = output_name(&args.output_name, &file, args)?;
+            let output = PathBuf::from(&args.output)
+                .join(relative.parent().unwrap_or(Path::new("")))
+                .join(name);
+            jobs.push((
+                file.to_string_lossy().into_owned(),
+                output.to_string_lossy().into_owned(),
+            ));
+        }
+
+        // inputs must not overwrite outputs of each other, e.g. `cube.obj` and `cube.stl` saved with `--format`
+        let mut outputs: HashMap<&str, &str> = HashMap::new();
+        for (input, output) in &jobs {
+            if let Some(other) = outputs.insert(output, input) {
+                return Err(format!(
+                    "Inputs {other} and {input} have the same output file {output}, change the output name template"
+                ));
+            }
+        }
+        Ok(jobs)
+    } else {
+        Err(format!("Input {} does not exist", args.input))
+    }
+}
+
+/// Collect files of the directory and optionally of its subdirectories.
+fn collect_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = dir
+        .read_dir()
+        .map_err(|e| format!("Failed to read directory {}: {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read directory {}: {e}", dir.display()))?
+            .path();
+        if path.is_file() {
+            files.push(path);
+        } else if recursive && path.is_dir() {
+            collect_files(&path, recursive, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compile glob patterns, no patterns means no filter.
+fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, String> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| format!("Invalid glob pattern: {e}"))?);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Invalid glob pattern: {e}"))
+}
+
+/// Output file name by the template like `{stem}_c{count}.{ext}`.
+fn output_name(template: &str, input: &Path, args: &CantellateArgs) -> Result<String, String> {
+    let options = &args.options;
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in output name template `{template}`"))?;
+        let value = match &rest[start + 1..start + end] {
+            "stem" => input
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            "ext" => match args.format {
+                Some(format) => format.extension().to_owned(),
+                None => input
+                    .extension()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            "count" => options.count.to_string(),
+            "factor" => options.factor.to_string(),
+            "segments" => options.segments.to_string(),
+            // iteration is substituted when the mesh is saved
+            "iter" => ITER.to_owned(),
+            placeholder => {
+                return Err(format!(
+                    "Unknown placeholder `{{{placeholder}}}` in output name template `{template}`"
+                ))
+            }
+        };
+        name.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+/// OBJ group of selected faces of the output mesh.
+const SELECTED_GROUP: &str = "selected";
+
+/// Placeholder of the iteration number in output paths.
+const ITER: &str = "{iter}";
+
+/// Iterations to save: the last one and each `--save-every` iteration if the output has the `{iter}` placeholder.
+fn saved_iterations(args: &CantellateArgs, output: &str) -> Result<Vec<usize>, String> {
+    let count = args.options.count;
+    if !output.contains(ITER) {
+        if args.save_every.is_some() {
+            return Err(format!(
+                "Output {output} must contain {ITER} placeholder to save intermediate iterations"
+            ));
+        }
+        return Ok(vec![count]);
+    }
+    let every = args.save_every.unwrap_or(1).max(1);
+    let mut iterations: Vec<usize> = (1..count).filter(|i| i % every == 0).collect();
+    iterations.push(count);
+    Ok(iterations)
+}
+
+/// Output path of the iteration.
+fn iteration_output(output: &str, iteration: usize) -> String {
+    output.replace(ITER, &iteration.to_string())
+}
+
+/// Apply the policy for existing output files and print the plan in the dry run.
+fn apply_on_existing(
+    args: &CantellateArgs,
+    mut jobs: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, String> {
+    // all files written by the job
+    let outputs = |output: &str| -> Result<Vec<String>, String> {
+        Ok(saved_iterations(args, output)?
+            .into_iter()
+            .map(|iteration| iteration_output(output, iteration))
+            .collect())
+    };
+    let existing = |output: &str| -> Result<Option<String>, String> {
+        Ok(outputs(output)?
+            .into_iter()
+            .find(|output| PathBuf::from(output).exists()))
+    };
+    let is_dir = PathBuf::from(&args.input).is_dir();
+    let on_existing = args.on_existing.unwrap_or(if is_dir {
+        OnExisting::Error
+    } else {
+        OnExisting::OverwriteFiles
+    });
+    let clean = on_existing == OnExisting::Clean && is_dir;
+    match on_existing {
+        OnExisting::Error => {
+            for (_, output) in &jobs {
+                if let Some(output) = existing(output)? {
+                    return Err(format!(
+                        "Output file {output} already exists, choose another policy by --on-existing"
+                    ));
+                }
+            }
+        }
+        OnExisting::OverwriteFiles => {}
+        OnExisting::Skip => {
+            let mut kept = Vec::new();
+            for (input, output) in jobs {
+                if let Some(output) = existing(&output)? {
+                    log::info!("Skip {input}: output {output} already exists");
                 } else {
-                    run::<f32>(&args);
+                    kept.push((input, output));
                 }
             }
+            jobs = kept;
         }
+        OnExisting::Clean => {
+            // `{iter}` is usually in output names, the directory has it only if each iteration has its own directory
+            let output_dirs = if args.output.contains(ITER) {
+                outputs(&args.output)?
+            } else {
+                vec![args.output.clone()]
+            };
+            for output_dir in output_dirs {
+                let output_dir = PathBuf::from(output_dir);
+                if !clean || !output_dir.exists() {
+                    continue;
+                }
+                // never remove the input together with the output
+                let input_dir = PathBuf::from(&args.input)
+                    .canonicalize()
+                    .map_err(|e| format!("Failed to resolve input directory: {e}"))?;
+                let output_dir = output_dir
+                    .canonicalize()
+                    .map_err(|e| format!("Failed to resolve output directory: {e}"))?;
+                if input_dir.starts_with(&output_dir) {
+                    return Err(format!(
+                        "Output directory {} contains the input, it can't be cleaned",
+                        output_dir.display()
+                    ));
+                }
+
+                if args.dry_run {
+                    println!("remove {}", output_dir.display());
+                } else {
+                    log::info!("Remove output directory {}", output_dir.display());
+                    std::fs::remove_dir_all(&output_dir)
+                        .map_err(|e| format!("Failed to remove output directory: {e}"))?;
+                }
+            }
+        }
+    }
+
+    if args.dry_run {
+        for (input, output) in &jobs {
+            for output in outputs(output)? {
+                let action = if PathBuf::from(&output).exists() && !clean {
+                    "overwrite"
+                } else {
+                    "write"
+                };
+                println!("{action} {output} from {input}");
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+/// Load the mesh from the file or generate the built-in primitive.
+fn load_mesh<N>(input: &str) -> Result<Mesh<N>, String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    if let Some(spec) = input.strip_prefix("gen:") {
+        Ok(Mesh::generate(&spec.parse::<Primitive>()?))
+    } else {
+        Mesh::load(input)
+    }
+}
+
+/// Load the mesh with the group name of each face, groups are read from OBJ files only.
+fn load_mesh_groups<N>(input: &str) -> Result<(Mesh<N>, Vec<String>), String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    match Format::from_path(input) {
+        Ok(Format::Obj) => Mesh::load_obj_groups(input),
+        _ => Ok((load_mesh(input)?, Vec::new())),
+    }
+}
+
+/// Save the mesh, creating the parent directory if needed.
+/// Group names of faces are saved to OBJ files only.
+fn save_mesh<N>(mesh: &Mesh<N>, groups: &[String], output: &str) -> Result<(), String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let output_path = PathBuf::from(output);
+    if let Some(output_dir) = output_path.parent() {
+        if !output_dir.as_os_str().is_empty() && !output_dir.exists() {
+            std::fs::create_dir_all(output_dir)
+                .map_err(|e| format!("Failed to create output directory: {e}"))?;
+        }
+    }
+    match Format::from_path(&output_path) {
+        Ok(Format::Obj) if !groups.is_empty() => mesh.save_obj_groups(output_path, groups),
+        _ => mesh.save(output_path),
     }
 }
 
-// Run the demo.
-fn run<N>(args: &Args)
+/// Print statistics of the input mesh and of the cantellated mesh.
+fn run_stats<N>(args: &StatsArgs, common: &CommonOptions)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mesh = load_mesh::<N>(&args.input).unwrap_or_else(|e| exit_with_error(&e));
+    let params = args
+        .options
+        .params(common.tolerances(&mesh))
+        .unwrap_or_else(|e| exit_with_error(&e));
+
+    let input = MeshStats::new(&mesh, &params.tolerances);
+    let mut selected = args
+        .options
+        .selected(&args.input, &mesh, params.tolerances.face_normal())
+        .unwrap_or_else(|e| exit_with_error(&e));
+    let output = if args.options.count > 0 {
+        let mesh = args.options.weld(mesh, &mut selected);
+        let count = args.options.count;
+        let output_mesh = (0..count)
+            .try_fold(mesh, |mesh, iteration| {
+                args.options
+                    .cantellate(&mesh, &params, &mut selected, iteration + 1 == count)
+            })
+            .unwrap_or_else(|e| exit_with_error(&e));
+        let output_mesh = subdivide(
+            &output_mesh,
+            args.options.subdivision,
+            args.options.subdivide,
+        );
+        Some(MeshStats::new(&output_mesh, &params.tolerances))
+    } else {
+        None
+    };
+
+    if args.json {
+        let report = StatsReport { input, output };
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!("Input: {}\n{input}", args.input);
+        if let Some(output) = output {
+            println!(
+                "Cantellated ({} iterations, factor {}):\n{output}",
+                args.options.count, args.options.factor
+            );
+        }
+    }
+}
+
+/// Print problems of the mesh and save the repaired mesh if requested.
+fn run_validate<N>(args: &ValidateArgs)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mesh = load_mesh::<N>(&args.input).unwrap_or_else(|e| exit_with_error(&e));
+    let report = ValidationReport::new(&mesh);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!("Input: {}\n{report}", args.input);
+    }
+
+    if let Some(output) = &args.repair {
+        let repaired = repair(&mesh);
+        log::info!(
+            "Repaired mesh: vertices count: {}, faces count: {}",
+            repaired.vertices.len(),
+            repaired.faces.len()
+        );
+        save_mesh(&repaired, &[], output).unwrap_or_else(|e| exit_with_error(&e));
+    }
+}
+
+/// Cantellate the input file and save iterations to the output.
+/// Returns faces count of the input and of the output mesh.
+fn run<N>(args: &CantellateArgs, common: &CommonOptions) -> Result<(usize, usize), String>
 where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
     log::info!("Input mesh: {}, output {}", args.input, args.output);
-    // load the input mesh
-    let mesh = Mesh::<N>::load_obj(&args.input).unwrap();
+    // load or generate the input mesh
+    let mut mesh = load_mesh::<N>(&args.input)?;
+    let params = args.options.params(common.tolerances(&mesh))?;
+    // the selection is done on the loaded mesh, so face indices and groups match the input file
+    let mut selected =
+        args.options
+            .selected(&args.input, &mesh, params.tolerances.face_normal())?;
+    if selected.is_some() && args.uncantellate {
+        return Err("Selection can't be combined with --uncantellate".to_owned());
+    }
+    if args.options.frame.is_some() && args.uncantellate {
+        return Err("Frame can't be combined with --uncantellate".to_owned());
+    }
+    if args.options.hull && (args.uncantellate || args.options.frame.is_some()) {
+        return Err("Hull can't be combined with --uncantellate or --frame".to_owned());
+    }
+    if args.repair {
+        let (repaired, kept) = repair_faces(&mesh);
+        mesh = repaired;
+        if let Some(selected) = &mut selected {
+            *selected = kept
+                .iter()
+                .map(|&face_index| selected[face_index])
+                .collect();
+        }
+    }
+    let mesh = args.options.weld(mesh, &mut selected);
+    let input_faces = mesh.faces.len();
+    let saved_iterations = saved_iterations(args, &args.output)?;
+    // selected faces are saved to the `selected` group
+    let save_iteration = |mesh: &Mesh<N>,
+                          selected: Option<&[bool]>,
+                          iteration: usize|
+     -> Result<(), String> {
+        let output = iteration_output(&args.output, iteration);
+        let (split, sources) = split_faces(mesh, args.output_faces, &params.tolerances);
+        let groups: Vec<String> = match selected {
+            Some(selected) if selected.len() == mesh.faces.len() => sources
+                .iter()
+                .map(|&face_index| {
+                    let group = if selected[face_index] {
+                        SELECTED_GROUP
+                    } else {
+                        DEFAULT_GROUP
+                    };
+                    group.to_owned()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mesh = &split;
+        save_mesh(mesh, &groups, &output)?;
+        if args.output.contains(ITER) {
+            let stats = MeshStats::new(mesh, &params.tolerances);
+            log::info!(
+                "Iteration {iteration} saved to {output}; edges count: {}, euler characteristic: {}, surface area: {:.6}, volume: {:.6}",
+                stats.edges,
+                stats.euler_characteristic,
+                stats.surface_area,
+                stats.volume
+            );
+        }
+        Ok(())
+    };
 
     // do the cantellation
-    let output_mesh = (0..args.count).fold(mesh, |mesh, iteration| {
+    let output_mesh = (0..args.options.count).try_fold(mesh, |mesh, iteration| {
         let timer = std::time::Instant::now();
-        let result = cantellate(
-            &mesh,
-            N::from_f32(args.factor).unwrap(),
-            N::from_f32(args.epsilon).unwrap(),
-        );
+        let result = if args.uncantellate {
+            let uncantellated = uncantellate(&mesh, &params.tolerances)?;
+            log::info!(
+                "Iteration {} recovered factor {}",
+                iteration + 1,
+                uncantellated.factor.to_f64().unwrap_or_default()
+            );
+            uncantellated.mesh
+        } else {
+            let is_last = iteration + 1 == args.options.count;
+            args.options
+                .cantellate(&mesh, &params, &mut selected, is_last)?
+        };
         log::info!(
-            "Iteration {} took {:?}; vertices count: {}",
+            "Iteration {} took {:?}; vertices count: {}, faces count: {}",
             iteration + 1,
             timer.elapsed(),
-            result.vertices.len()
+            result.vertices.len(),
+            result.faces.len()
         );
-        result
-    });
-
-    let output_path: PathBuf = args.output.clone().into();
-    if let Some(output_dir) = output_path.parent() {
-        if !output_dir.exists() {
-            std::fs::create_dir_all(output_dir).unwrap();
+        // save intermediate iterations, the last one is saved below
+        if iteration + 1 < args.options.count && saved_iterations.contains(&(iteration + 1)) {
+            save_iteration(&result, selected.as_deref(), iteration + 1)?;
         }
-    }
+        Ok::<_, String>(result)
+    })?;
 
-    // save the output mesh
-    output_mesh.save_obj(&args.output).unwrap();
+    // subdivide and save the output mesh, faces of the subdivision have no selection
+    let output_mesh = if args.options.subdivide > 0 {
+        selected = None;
+        subdivide(
+            &output_mesh,
+            args.options.subdivision,
+            args.options.subdivide,
+        )
+    } else {
+        output_mesh
+    };
+    save_iteration(&output_mesh, selected.as_deref(), args.options.count)?;
+    Ok((input_faces, output_mesh.faces.len()))
 }
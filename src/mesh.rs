@@ -4,6 +4,9 @@ use std::{path::Path, vec};
 
 pub type SmallVec<T> = smallvec::SmallVec<[T; 4]>;
 
+/// Name of the OBJ group of faces without the `g` statement.
+pub const DEFAULT_GROUP: &str = "default";
+
 #[derive(Debug, Default, Clone)]
 pub struct Mesh<N>
 where
@@ -11,6 +14,13 @@ where
 {
     pub vertices: Vec<Vec3<N>>,
     pub faces: Vec<SmallVec<usize>>,
+
+    /// Texture coordinates of face corners, empty if the mesh has no UVs.
+    pub texcoords: Vec<[N; 2]>,
+
+    /// Indices of texture coordinates of each face corner. It's empty if the mesh has no UVs,
+    /// otherwise it has an entry for each face, empty for faces without UVs.
+    pub face_texcoords: Vec<SmallVec<usize>>,
 }
 
 impl<N> Mesh<N>
@@ -18,52 +28,182 @@ where
     N: Float + ToPrimitive + FromPrimitive + Default,
 {
     pub fn load_obj(path: impl AsRef<Path>) -> Result<Self, String> {
-        let obj_data = obj::Obj::load(path).map_err(|e| format!("Failed to load OBJ file: {e}"))?;
-        let vertices: Vec<Vec3<N>> = obj_data
-            .data
-            .position
-            .iter()
-            .cloned()
-            .map(Into::into)
-            .collect();
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to load OBJ file: {e}"))?;
+        Self::parse_obj(&text)
+    }
+
+    /// Parse the text of the OBJ file. All objects and groups are united into a single mesh.
+    /// Only vertex positions, texture coordinates and faces are read, other statements are ignored.
+    /// Indices out of the vertices range are kept as is, see `ValidationReport`.
+    pub fn parse_obj(text: &str) -> Result<Self, String> {
+        Self::parse_obj_groups(text).map(|(mesh, _)| mesh)
+    }
+
+    /// Load the OBJ file with the group name of each face.
+    pub fn load_obj_groups(path: impl AsRef<Path>) -> Result<(Self, Vec<String>), String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to load OBJ file: {e}"))?;
+        Self::parse_obj_groups(&text)
+    }
+
+    /// Parse the OBJ file with the group name of each face.
+    /// Faces before the first `g` statement and after each `o` statement are in the `default` group.
+    /// The `obj` crate is only used for saving: its loader panics on non-ASCII whitespace before `o` and `g`,
+    /// wraps relative indices before the first vertex into huge indices and rejects statements it doesn't know.
+    pub fn parse_obj_groups(text: &str) -> Result<(Self, Vec<String>), String> {
+        let mut mesh = Self::default();
+        let mut face_texcoords = Vec::new();
+        let mut groups = Vec::new();
+        let mut group = DEFAULT_GROUP.to_owned();
+        for (line_index, line) in text.lines().enumerate() {
+            let error = |message: &str| {
+                format!(
+                    "Failed to load OBJ file: {message} at line {}",
+                    line_index + 1
+                )
+            };
+            let mut words = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let mut coordinate = || -> Result<f32, String> {
+                        words
+                            .next()
+                            .and_then(|word| word.parse().ok())
+                            .ok_or_else(|| error("invalid vertex"))
+                    };
+                    let position = [coordinate()?, coordinate()?, coordinate()?];
+                    mesh.vertices.push(position.into());
+                }
+                Some("vt") => {
+                    let mut coordinate = |default: Option<f32>| -> Result<N, String> {
+                        words
+                            .next()
+                            .map_or(default, |word| word.parse().ok())
+                            .and_then(N::from_f32)
+                            .ok_or_else(|| error("invalid texture coordinate"))
+                    };
+                    // the second coordinate is optional for 1D textures
+                    let texcoord = [coordinate(None)?, coordinate(Some(0.0))?];
+                    mesh.texcoords.push(texcoord);
+                }
+                Some("f") => {
+                    // negative indices are relative to the end of the values read so far
+                    let parse_index =
+                        |index: Option<&str>, count: usize| -> Result<usize, String> {
+                            let index: isize = index
+                                .and_then(|index| index.parse().ok())
+                                .ok_or_else(|| error("invalid face index"))?;
+                            match index {
+                                0 => Err(error("zero face index")),
+                                1.. => Ok(index.unsigned_abs() - 1),
+                                _ => count
+                                    .checked_sub(index.unsigned_abs())
+                                    .ok_or_else(|| error("relative face index out of range")),
+                            }
+                        };
+                    let mut face = SmallVec::new();
+                    let mut texcoords = SmallVec::new();
+                    for word in words {
+                        // position and texture indices of `v/vt/vn` are used
+                        let mut indices = word.split('/');
+                        face.push(parse_index(indices.next(), mesh.vertices.len())?);
+                        if let Some(texcoord) = indices.next().filter(|index| !index.is_empty()) {
+                            texcoords.push(parse_index(Some(texcoord), mesh.texcoords.len())?);
+                        }
+                    }
+                    // UVs of some corners only are ignored
+                    if texcoords.len() != face.len() {
+                        texcoords.clear();
+                    }
+                    mesh.faces.push(face);
+                    face_texcoords.push(texcoords);
+                    groups.push(group.clone());
+                }
+                Some("g") => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    group = if name.is_empty() {
+                        DEFAULT_GROUP.to_owned()
+                    } else {
+                        name
+                    };
+                }
+                Some("o") => group = DEFAULT_GROUP.to_owned(),
+                _ => {}
+            }
+        }
+        if face_texcoords.iter().any(|texcoords| !texcoords.is_empty()) {
+            mesh.face_texcoords = face_texcoords;
+        }
+        Ok((mesh, groups))
+    }
 
-        // unite all objects and groups into a single mesh
-        let faces: Vec<SmallVec<_>> = obj_data
-            .data
-            .objects
-            .iter()
-            .flat_map(|object| object.groups.iter())
-            .flat_map(|group| group.polys.iter())
-            .map(|poly| poly.0.iter().map(|index_tuple| index_tuple.0).collect())
-            .collect();
+    /// Texture coordinate indices of face corners, `None` if the face has no UVs.
+    pub fn face_texcoords(&self, face_index: usize) -> Option<&[usize]> {
+        self.face_texcoords
+            .get(face_index)
+            .map(|texcoords| texcoords.as_slice())
+            .filter(|texcoords| !texcoords.is_empty())
+    }
 
-        Ok(Self { vertices, faces })
+    /// Area of the face. For non-planar faces it's an area of the projection to the mean plane.
+    pub fn face_area(&self, face_index: usize) -> N {
+        let face = &self.faces[face_index];
+        let doubled = (0..face.len()).fold(Vec3::zero(), |sum, i| {
+            let a = self.vertices[face[i]];
+            let b = self.vertices[face[(i + 1) % face.len()]];
+            sum + a.cross(b)
+        });
+        doubled.length() / (N::one() + N::one())
     }
 
     pub fn save_obj(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        self.save_obj_groups(path, &[])
+    }
+
+    /// Save the OBJ file with the group name of each face, faces without the name are in the `default` group.
+    /// Consecutive faces of the same group are written in a single `g` statement.
+    pub fn save_obj_groups(&self, path: impl AsRef<Path>, groups: &[String]) -> Result<(), String> {
         let path = path.as_ref();
-        let obj_group = obj::Group {
-            name: "default".to_owned(),
-            polys: self
-                .faces
-                .iter()
-                .map(|face| {
-                    obj::SimplePolygon(
-                        face.iter()
-                            .map(|&index| obj::IndexTuple(index, None, None))
-                            .collect(),
-                    )
-                })
-                .collect(),
-            index: 0,
-            material: None,
-        };
+        let mut obj_groups: Vec<obj::Group> = Vec::new();
+        for (face_index, face) in self.faces.iter().enumerate() {
+            let name = groups.get(face_index).map_or(DEFAULT_GROUP, String::as_str);
+            if obj_groups.last().map(|group| group.name.as_str()) != Some(name) {
+                obj_groups.push(obj::Group {
+                    name: name.to_owned(),
+                    index: 0,
+                    material: None,
+                    polys: Vec::new(),
+                });
+            }
+            let texcoords = self.face_texcoords(face_index);
+            let poly = obj::SimplePolygon(
+                face.iter()
+                    .enumerate()
+                    .map(|(corner, &index)| {
+                        obj::IndexTuple(index, texcoords.map(|t| t[corner]), None)
+                    })
+                    .collect(),
+            );
+            if let Some(group) = obj_groups.last_mut() {
+                group.polys.push(poly);
+            }
+        }
         let obj_data = obj::Obj {
             data: obj::ObjData {
                 position: self.vertices.iter().cloned().map(Into::into).collect(),
+                texture: self
+                    .texcoords
+                    .iter()
+                    .map(|uv| uv.map(|value| value.to_f32().unwrap_or_default()))
+                    .collect(),
                 objects: vec![obj::Object {
                     name: "mesh".to_owned(),
-                    groups: vec![obj_group],
+                    groups: obj_groups,
                 }],
                 ..Default::default()
             },
@@ -0,0 +1,181 @@
+use crate::{cantellate::get_faces_normal, mesh::Mesh, tolerance::Tolerances, vec3::Vec3};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use serde::Serialize;
+use std::fmt;
+
+/// Normals of faces used to move face corners by the cantellation.
+///
+/// The normal of a face is the normalized sum of cross products of its corners.
+/// Degenerate faces, like slivers and zero-area faces, take the average normal of neighbour faces
+/// which share vertices with them, so their corners move together with the neighbours instead of staying in place.
+/// Non-planar faces take the normal of the best-fit plane of their vertices if they deviate more than `Tolerances::fit_plane`.
+#[derive(Debug, Clone)]
+pub struct FaceNormals<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Normal of each face, `None` if the face has invalid indices or no neighbour has a normal.
+    pub normals: Vec<Option<Vec3<N>>>,
+
+    /// Faces whose normals are not calculated by their own corners.
+    pub report: NormalsReport,
+}
+
+/// Faces with fallback normals.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NormalsReport {
+    /// Degenerate faces with the average normal of neighbour faces.
+    pub fallback: Vec<usize>,
+
+    /// Degenerate faces without neighbours with normals, their corners are not moved.
+    pub unresolved: Vec<usize>,
+
+    /// Faces whose vertices are farther from the plane than the fit plane tolerance, they have the best-fit normal.
+    pub non_planar: Vec<usize>,
+}
+
+impl<N> FaceNormals<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    pub fn new(mesh: &Mesh<N>, tolerances: &Tolerances<N>) -> Self {
+        let mut normals = get_faces_normal(mesh, tolerances.face_normal());
+        let mut report = NormalsReport::default();
+
+        // best-fit planes of non-planar faces
+        for (face_index, face) in mesh.faces.iter().enumerate() {
+            let Some(normal) = normals[face_index] else {
+                continue;
+            };
+            if face.len() <= 3 {
+                continue;
+            }
+            let points: Vec<Vec3<N>> = face.iter().map(|&index| mesh.vertices[index]).collect();
+            let centroid = points.iter().fold(Vec3::zero(), |sum, &point| sum + point)
+                / N::from_usize(points.len()).unwrap();
+            let is_planar = points
+                .iter()
+                .all(|&point| (point - centroid).dot(normal).abs() <= tolerances.fit_plane);
+            if !is_planar {
+                if let Some(fitted) = fit_plane_normal(&points, centroid, normal) {
+                    normals[face_index] = Some(fitted);
+                    report.non_planar.push(face_index);
+                }
+            }
+        }
+
+        // degenerate faces with valid indices take normals of neighbours, repeated while normals spread
+        let mut vertex_faces = vec![Vec::new(); mesh.vertices.len()];
+        for (face_index, face) in mesh.faces.iter().enumerate() {
+            for &index in face {
+                if let Some(faces) = vertex_faces.get_mut(index) {
+                    faces.push(face_index);
+                }
+            }
+        }
+        let mut degenerate: Vec<usize> = (0..mesh.faces.len())
+            .filter(|&face_index| {
+                let face = &mesh.faces[face_index];
+                normals[face_index].is_none()
+                    && face.len() >= 3
+                    && face.iter().all(|&index| index < mesh.vertices.len())
+            })
+            .collect();
+        loop {
+            let resolved: Vec<(usize, Vec3<N>)> = degenerate
+                .iter()
+                .filter_map(|&face_index| {
+                    let sum = mesh.faces[face_index]
+                        .iter()
+                        .flat_map(|&index| &vertex_faces[index])
+                        .filter_map(|&other| normals[other])
+                        .fold(Vec3::zero(), |sum, normal| sum + normal);
+                    Some((face_index, sum.normalize(tolerances.angle)?))
+                })
+                .collect();
+            if resolved.is_empty() {
+                break;
+            }
+            for (face_index, normal) in resolved {
+                normals[face_index] = Some(normal);
+                report.fallback.push(face_index);
+            }
+            degenerate.retain(|&face_index| normals[face_index].is_none());
+        }
+        report.fallback.sort_unstable();
+        report.unresolved = degenerate;
+
+        Self { normals, report }
+    }
+}
+
+/// Normal of the least squares plane through the points, oriented as the guess.
+/// The normal is the axis of the smallest spread of points around the centroid,
+/// it's found by the largest determinant of the covariance matrix minor.
+fn fit_plane_normal<N>(points: &[Vec3<N>], centroid: Vec3<N>, guess: Vec3<N>) -> Option<Vec3<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let zero = N::zero();
+    let (mut xx, mut xy, mut xz, mut yy, mut yz, mut zz) = (zero, zero, zero, zero, zero, zero);
+    for &point in points {
+        let r = point - centroid;
+        xx = xx + r.x * r.x;
+        xy = xy + r.x * r.y;
+        xz = xz + r.x * r.z;
+        yy = yy + r.y * r.y;
+        yz = yz + r.y * r.z;
+        zz = zz + r.z * r.z;
+    }
+
+    let det_x = yy * zz - yz * yz;
+    let det_y = xx * zz - xz * xz;
+    let det_z = xx * yy - xy * xy;
+    let normal = if det_x >= det_y && det_x >= det_z {
+        Vec3::new(det_x, xz * yz - xy * zz, xy * yz - xz * yy)
+    } else if det_y >= det_z {
+        Vec3::new(xz * yz - xy * zz, det_y, xy * xz - yz * xx)
+    } else {
+        Vec3::new(xy * yz - xz * yy, xy * xz - yz * xx, det_z)
+    };
+    let normal = normal.normalize(zero)?;
+    if normal.dot(guess) < zero {
+        Some(normal * -N::one())
+    } else {
+        Some(normal)
+    }
+}
+
+impl NormalsReport {
+    pub fn is_empty(&self) -> bool {
+        self.fallback.is_empty() && self.unresolved.is_empty() && self.non_planar.is_empty()
+    }
+}
+
+impl fmt::Display for NormalsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // print only first items of long lists
+        fn list(items: &[usize]) -> String {
+            const LIMIT: usize = 10;
+            let shown: Vec<_> = items.iter().take(LIMIT).map(usize::to_string).collect();
+            let more = if items.len() > LIMIT { ", ..." } else { "" };
+            format!("{} [{}{more}]", items.len(), shown.join(", "))
+        }
+
+        writeln!(
+            f,
+            "  degenerate faces with normals of neighbours: {}",
+            list(&self.fallback)
+        )?;
+        writeln!(
+            f,
+            "  degenerate faces which are not moved: {}",
+            list(&self.unresolved)
+        )?;
+        write!(
+            f,
+            "  non-planar faces with best-fit normals: {}",
+            list(&self.non_planar)
+        )
+    }
+}
@@ -0,0 +1,705 @@
+use crate::{
+    mesh::{Mesh, SmallVec},
+    vec3::Vec3,
+};
+use ahash::AHashMap;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use std::{f64::consts::PI, str::FromStr};
+
+/// Built-in primitive shape with its parameters.
+/// All shapes are centered at the origin, `z` axis is up and faces are oriented counter-clockwise
+/// when viewed from outside, the same way as in the `.obj` assets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Tetrahedron {
+        radius: f64,
+    },
+    Cube {
+        size: f64,
+    },
+    Octahedron {
+        radius: f64,
+    },
+    Dodecahedron {
+        radius: f64,
+    },
+    Icosahedron {
+        radius: f64,
+    },
+    Prism {
+        sides: usize,
+        radius: f64,
+        height: f64,
+    },
+    Antiprism {
+        sides: usize,
+        radius: f64,
+        height: f64,
+    },
+    Pyramid {
+        sides: usize,
+        radius: f64,
+        height: f64,
+    },
+    UvSphere {
+        radius: f64,
+        segments: usize,
+        rings: usize,
+    },
+    Icosphere {
+        radius: f64,
+        subdivisions: usize,
+    },
+    Torus {
+        major_radius: f64,
+        minor_radius: f64,
+        major_segments: usize,
+        minor_segments: usize,
+    },
+    Grid {
+        width: f64,
+        depth: f64,
+        x_segments: usize,
+        y_segments: usize,
+    },
+}
+
+impl Primitive {
+    /// Names of all primitives accepted by `Primitive::from_str`.
+    pub const NAMES: &'static [&'static str] = &[
+        "tetrahedron",
+        "cube",
+        "octahedron",
+        "dodecahedron",
+        "icosahedron",
+        "prism",
+        "antiprism",
+        "pyramid",
+        "uv_sphere",
+        "icosphere",
+        "torus",
+        "grid",
+    ];
+}
+
+/// Parse primitive from the spec like `icosahedron` or `prism:sides=6,height=2`.
+/// Omitted parameters take default values.
+impl FromStr for Primitive {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, params) = spec.split_once(':').unwrap_or((spec, ""));
+        let mut params = PrimitiveParams::parse(params)?;
+        let primitive = match name {
+            "tetrahedron" => Primitive::Tetrahedron {
+                radius: params.float("radius", 1.0)?,
+            },
+            "cube" => Primitive::Cube {
+                size: params.float("size", 1.0)?,
+            },
+            "octahedron" => Primitive::Octahedron {
+                radius: params.float("radius", 1.0)?,
+            },
+            "dodecahedron" => Primitive::Dodecahedron {
+                radius: params.float("radius", 1.0)?,
+            },
+            "icosahedron" => Primitive::Icosahedron {
+                radius: params.float("radius", 1.0)?,
+            },
+            "prism" => Primitive::Prism {
+                sides: params.count("sides", 6, 3)?,
+                radius: params.float("radius", 1.0)?,
+                height: params.float("height", 1.0)?,
+            },
+            "antiprism" => Primitive::Antiprism {
+                sides: params.count("sides", 6, 3)?,
+                radius: params.float("radius", 1.0)?,
+                height: params.float("height", 1.0)?,
+            },
+            "pyramid" => Primitive::Pyramid {
+                sides: params.count("sides", 4, 3)?,
+                radius: params.float("radius", 1.0)?,
+                height: params.float("height", 1.0)?,
+            },
+            "uv_sphere" => Primitive::UvSphere {
+                radius: params.float("radius", 1.0)?,
+                segments: params.count("segments", 16, 3)?,
+                rings: params.count("rings", 8, 2)?,
+            },
+            "icosphere" => Primitive::Icosphere {
+                radius: params.float("radius", 1.0)?,
+                subdivisions: params.count("subdivisions", 2, 0)?,
+            },
+            "torus" => {
+                let major_radius = params.float("major_radius", 1.0)?;
+                let minor_radius = params.float("minor_radius", 0.25)?;
+                // the tube must not cross the axis of the torus
+                if minor_radius >= major_radius {
+                    return Err(format!(
+                        "`minor_radius` must be less than `major_radius`, got {minor_radius} and {major_radius}"
+                    ));
+                }
+                Primitive::Torus {
+                    major_radius,
+                    minor_radius,
+                    major_segments: params.count("major_segments", 24, 3)?,
+                    minor_segments: params.count("minor_segments", 12, 3)?,
+                }
+            }
+            "grid" => Primitive::Grid {
+                width: params.float("width", 1.0)?,
+                depth: params.float("depth", 1.0)?,
+                x_segments: params.count("x_segments", 4, 1)?,
+                y_segments: params.count("y_segments", 4, 1)?,
+            },
+            _ => {
+                return Err(format!(
+                    "Unknown primitive `{name}`, expected one of: {}",
+                    Primitive::NAMES.join(", ")
+                ))
+            }
+        };
+        params.finish(name)?;
+        Ok(primitive)
+    }
+}
+
+/// Help structure to parse `key=value` parameters of the primitive spec.
+struct PrimitiveParams<'a> {
+    /// Parsed parameters. The flag is set when the parameter is used by the primitive.
+    values: Vec<(&'a str, &'a str, bool)>,
+}
+
+impl<'a> PrimitiveParams<'a> {
+    fn parse(params: &'a str) -> Result<Self, String> {
+        let values = params
+            .split(',')
+            .filter(|param| !param.trim().is_empty())
+            .map(|param| {
+                param
+                    .split_once('=')
+                    .map(|(key, value)| (key.trim(), value.trim(), false))
+                    .ok_or_else(|| {
+                        format!("Invalid primitive parameter `{param}`, expected `key=value`")
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { values })
+    }
+
+    fn take(&mut self, key: &str) -> Option<&'a str> {
+        self.values
+            .iter_mut()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, value, used)| {
+                *used = true;
+                *value
+            })
+    }
+
+    /// Size, radius or height, it must be finite and positive.
+    fn float(&mut self, key: &str, default: f64) -> Result<f64, String> {
+        let value: f64 = match self.take(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid value `{value}` of `{key}`: {e}"))?,
+            None => default,
+        };
+        if !value.is_finite() || value <= 0.0 {
+            return Err(format!("`{key}` must be a positive number, got {value}"));
+        }
+        Ok(value)
+    }
+
+    fn count(&mut self, key: &str, default: usize, min: usize) -> Result<usize, String> {
+        let count = match self.take(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|e| format!("Invalid value `{value}` of `{key}`: {e}"))?,
+            None => default,
+        };
+        if count < min {
+            return Err(format!("`{key}` must be at least {min}, got {count}"));
+        }
+        Ok(count)
+    }
+
+    /// Check that all parameters are used by the primitive.
+    fn finish(self, name: &str) -> Result<(), String> {
+        match self.values.iter().find(|(_, _, used)| !used) {
+            Some((key, _, _)) => Err(format!("Unknown parameter `{key}` of primitive `{name}`")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<N> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Generate the mesh of the primitive.
+    pub fn generate(primitive: &Primitive) -> Self {
+        let n = |value: f64| N::from_f64(value).unwrap_or_default();
+        match *primitive {
+            Primitive::Tetrahedron { radius } => Self::tetrahedron(n(radius)),
+            Primitive::Cube { size } => Self::cube(n(size)),
+            Primitive::Octahedron { radius } => Self::octahedron(n(radius)),
+            Primitive::Dodecahedron { radius } => Self::dodecahedron(n(radius)),
+            Primitive::Icosahedron { radius } => Self::icosahedron(n(radius)),
+            Primitive::Prism {
+                sides,
+                radius,
+                height,
+            } => Self::prism(sides, n(radius), n(height)),
+            Primitive::Antiprism {
+                sides,
+                radius,
+                height,
+            } => Self::antiprism(sides, n(radius), n(height)),
+            Primitive::Pyramid {
+                sides,
+                radius,
+                height,
+            } => Self::pyramid(sides, n(radius), n(height)),
+            Primitive::UvSphere {
+                radius,
+                segments,
+                rings,
+            } => Self::uv_sphere(n(radius), segments, rings),
+            Primitive::Icosphere {
+                radius,
+                subdivisions,
+            } => Self::icosphere(n(radius), subdivisions),
+            Primitive::Torus {
+                major_radius,
+                minor_radius,
+                major_segments,
+                minor_segments,
+            } => Self::torus(
+                n(major_radius),
+                n(minor_radius),
+                major_segments,
+                minor_segments,
+            ),
+            Primitive::Grid {
+                width,
+                depth,
+                x_segments,
+                y_segments,
+            } => Self::grid(n(width), n(depth), x_segments, y_segments),
+        }
+    }
+
+    /// Regular tetrahedron with the given circumradius.
+    pub fn tetrahedron(radius: N) -> Self {
+        let vertices = [[1., 1., 1.], [1., -1., -1.], [-1., 1., -1.], [-1., -1., 1.]];
+        let faces: [&[usize]; 4] = [&[0, 1, 2], &[0, 1, 3], &[0, 2, 3], &[1, 2, 3]];
+        convex_from_cycles(&vertices, &faces, radius)
+    }
+
+    /// Axis-aligned cube with the given edge length.
+    pub fn cube(size: N) -> Self {
+        // vertex index bits are the signs of the coordinates: x is bit 0, y is bit 1, z is bit 2
+        let vertices: Vec<_> = (0..8)
+            .map(|i| {
+                let sign = |bit: usize| if i & (1 << bit) != 0 { 1. } else { -1. };
+                [sign(0), sign(1), sign(2)]
+            })
+            .collect();
+        let faces: [&[usize]; 6] = [
+            &[0, 2, 6, 4],
+            &[1, 3, 7, 5],
+            &[0, 1, 5, 4],
+            &[2, 3, 7, 6],
+            &[0, 1, 3, 2],
+            &[4, 5, 7, 6],
+        ];
+        // coordinates are scaled without the circumradius, so they stay exact
+        let half = size / constant(2.);
+        let vertices = vertices
+            .iter()
+            .map(|&[x, y, z]| Vec3::new(constant(x), constant(y), constant(z)) * half)
+            .collect();
+        oriented_convex(vertices, &faces)
+    }
+
+    /// Regular octahedron with the given circumradius.
+    pub fn octahedron(radius: N) -> Self {
+        let vertices = [
+            [1., 0., 0.],
+            [-1., 0., 0.],
+            [0., 1., 0.],
+            [0., -1., 0.],
+            [0., 0., 1.],
+            [0., 0., -1.],
+        ];
+        let faces: Vec<[usize; 3]> = (0..8)
+            .map(|i| [i & 1, 2 + ((i >> 1) & 1), 4 + ((i >> 2) & 1)])
+            .collect();
+        let faces: Vec<&[usize]> = faces.iter().map(|face| face.as_slice()).collect();
+        convex_from_cycles(&vertices, &faces, radius)
+    }
+
+    /// Regular dodecahedron with the given circumradius.
+    /// It's built as a dual of the icosahedron.
+    pub fn dodecahedron(radius: N) -> Self {
+        dual_of_convex(&Self::icosahedron(N::one()), radius)
+    }
+
+    /// Regular icosahedron with the given circumradius.
+    pub fn icosahedron(radius: N) -> Self {
+        let t = (1. + 5f64.sqrt()) / 2.;
+        let vertices = [
+            [-1., t, 0.],
+            [1., t, 0.],
+            [-1., -t, 0.],
+            [1., -t, 0.],
+            [0., -1., t],
+            [0., 1., t],
+            [0., -1., -t],
+            [0., 1., -t],
+            [t, 0., -1.],
+            [t, 0., 1.],
+            [-t, 0., -1.],
+            [-t, 0., 1.],
+        ];
+        let faces: [&[usize]; 20] = [
+            &[0, 11, 5],
+            &[0, 5, 1],
+            &[0, 1, 7],
+            &[0, 7, 10],
+            &[0, 10, 11],
+            &[1, 5, 9],
+            &[5, 11, 4],
+            &[11, 10, 2],
+            &[10, 7, 6],
+            &[7, 1, 8],
+            &[3, 9, 4],
+            &[3, 4, 2],
+            &[3, 2, 6],
+            &[3, 6, 8],
+            &[3, 8, 9],
+            &[4, 9, 5],
+            &[2, 4, 11],
+            &[6, 2, 10],
+            &[8, 6, 7],
+            &[9, 8, 1],
+        ];
+        convex_from_cycles(&vertices, &faces, radius)
+    }
+
+    /// Right prism over the regular polygon with the given circumradius.
+    pub fn prism(sides: usize, radius: N, height: N) -> Self {
+        let half = height / constant(2.);
+        let mut mesh = Self::default();
+        mesh.vertices.extend(ring(sides, radius, -half, N::zero()));
+        mesh.vertices.extend(ring(sides, radius, half, N::zero()));
+
+        mesh.faces.push((0..sides).rev().collect());
+        mesh.faces.push((sides..2 * sides).collect());
+        for j in 0..sides {
+            let next = (j + 1) % sides;
+            mesh.faces
+                .push([j, next, sides + next, sides + j].into_iter().collect());
+        }
+        mesh
+    }
+
+    /// Antiprism over the regular polygon with the given circumradius.
+    /// The top polygon is rotated by a half of the side.
+    pub fn antiprism(sides: usize, radius: N, height: N) -> Self {
+        let half = height / constant(2.);
+        let mut mesh = Self::default();
+        mesh.vertices.extend(ring(sides, radius, -half, N::zero()));
+        mesh.vertices
+            .extend(ring(sides, radius, half, constant(0.5)));
+
+        mesh.faces.push((0..sides).rev().collect());
+        mesh.faces.push((sides..2 * sides).collect());
+        for j in 0..sides {
+            let next = (j + 1) % sides;
+            mesh.faces.push([j, next, sides + j].into_iter().collect());
+            mesh.faces
+                .push([sides + j, next, sides + next].into_iter().collect());
+        }
+        mesh
+    }
+
+    /// Right pyramid over the regular polygon with the given circumradius.
+    pub fn pyramid(sides: usize, radius: N, height: N) -> Self {
+        let half = height / constant(2.);
+        let mut mesh = Self::default();
+        mesh.vertices.extend(ring(sides, radius, -half, N::zero()));
+        mesh.vertices.push(Vec3::new(N::zero(), N::zero(), half));
+
+        mesh.faces.push((0..sides).rev().collect());
+        for j in 0..sides {
+            let next = (j + 1) % sides;
+            mesh.faces.push([j, next, sides].into_iter().collect());
+        }
+        mesh
+    }
+
+    /// Sphere made of `segments` meridians and `rings` parallel bands.
+    /// Bands at the poles are triangles, others are quads.
+    pub fn uv_sphere(radius: N, segments: usize, rings: usize) -> Self {
+        let mut mesh = Self::default();
+        let top = 0;
+        mesh.vertices.push(Vec3::new(N::zero(), N::zero(), radius));
+        for i in 1..rings {
+            let theta = constant::<N>(PI) * constant(i as f64) / constant(rings as f64);
+            let ring_radius = radius * theta.sin();
+            mesh.vertices
+                .extend(ring(segments, ring_radius, radius * theta.cos(), N::zero()));
+        }
+        let bottom = mesh.vertices.len();
+        mesh.vertices.push(Vec3::new(N::zero(), N::zero(), -radius));
+
+        let ring_vertex = |i: usize, j: usize| 1 + i * segments + j % segments;
+        for j in 0..segments {
+            mesh.faces.push(
+                [top, ring_vertex(0, j), ring_vertex(0, j + 1)]
+                    .into_iter()
+                    .collect(),
+            );
+        }
+        for i in 0..rings - 2 {
+            for j in 0..segments {
+                mesh.faces.push(
+                    [
+                        ring_vertex(i, j),
+                        ring_vertex(i + 1, j),
+                        ring_vertex(i + 1, j + 1),
+                        ring_vertex(i, j + 1),
+                    ]
+                    .into_iter()
+                    .collect(),
+                );
+            }
+        }
+        for j in 0..segments {
+            mesh.faces.push(
+                [
+                    bottom,
+                    ring_vertex(rings - 2, j + 1),
+                    ring_vertex(rings - 2, j),
+                ]
+                .into_iter()
+                .collect(),
+            );
+        }
+        mesh
+    }
+
+    /// Sphere made by the recursive subdivision of the icosahedron.
+    /// Each subdivision splits every triangle into 4 triangles.
+    pub fn icosphere(radius: N, subdivisions: usize) -> Self {
+        let mut mesh = Self::icosahedron(radius);
+        for _ in 0..subdivisions {
+            let mut midpoints: AHashMap<(usize, usize), usize> = AHashMap::new();
+            let mut midpoint = |mesh: &mut Self, a: usize, b: usize| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let middle = (mesh.vertices[a] + mesh.vertices[b]) / constant(2.);
+                    mesh.vertices.push(middle * (radius / middle.length()));
+                    mesh.vertices.len() - 1
+                })
+            };
+
+            let faces = std::mem::take(&mut mesh.faces);
+            for face in faces {
+                let (a, b, c) = (face[0], face[1], face[2]);
+                let ab = midpoint(&mut mesh, a, b);
+                let bc = midpoint(&mut mesh, b, c);
+                let ca = midpoint(&mut mesh, c, a);
+                mesh.faces.push([a, ab, ca].into_iter().collect());
+                mesh.faces.push([b, bc, ab].into_iter().collect());
+                mesh.faces.push([c, ca, bc].into_iter().collect());
+                mesh.faces.push([ab, bc, ca].into_iter().collect());
+            }
+        }
+        mesh
+    }
+
+    /// Torus around the `z` axis made of quads.
+    pub fn torus(
+        major_radius: N,
+        minor_radius: N,
+        major_segments: usize,
+        minor_segments: usize,
+    ) -> Self {
+        let mut mesh = Self::default();
+        for i in 0..major_segments {
+            let u = constant::<N>(PI) * constant(2. * i as f64 / major_segments as f64);
+            for j in 0..minor_segments {
+                let v = constant::<N>(PI) * constant(2. * j as f64 / minor_segments as f64);
+                let distance = major_radius + minor_radius * v.cos();
+                mesh.vertices.push(Vec3::new(
+                    distance * u.cos(),
+                    distance * u.sin(),
+                    minor_radius * v.sin(),
+                ));
+            }
+        }
+
+        let vertex =
+            |i: usize, j: usize| (i % major_segments) * minor_segments + j % minor_segments;
+        for i in 0..major_segments {
+            for j in 0..minor_segments {
+                mesh.faces.push(
+                    [
+                        vertex(i, j),
+                        vertex(i + 1, j),
+                        vertex(i + 1, j + 1),
+                        vertex(i, j + 1),
+                    ]
+                    .into_iter()
+                    .collect(),
+                );
+            }
+        }
+        mesh
+    }
+
+    /// Flat grid of quads in the `xy` plane facing `+z`.
+    pub fn grid(width: N, depth: N, x_segments: usize, y_segments: usize) -> Self {
+        let mut mesh = Self::default();
+        for j in 0..=y_segments {
+            let y = depth * (constant::<N>(j as f64) / constant(y_segments as f64) - constant(0.5));
+            for i in 0..=x_segments {
+                let x =
+                    width * (constant::<N>(i as f64) / constant(x_segments as f64) - constant(0.5));
+                mesh.vertices.push(Vec3::new(x, y, N::zero()));
+            }
+        }
+
+        let vertex = |i: usize, j: usize| j * (x_segments + 1) + i;
+        for j in 0..y_segments {
+            for i in 0..x_segments {
+                mesh.faces.push(
+                    [
+                        vertex(i, j),
+                        vertex(i + 1, j),
+                        vertex(i + 1, j + 1),
+                        vertex(i, j + 1),
+                    ]
+                    .into_iter()
+                    .collect(),
+                );
+            }
+        }
+        mesh
+    }
+}
+
+fn constant<N>(value: f64) -> N
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    N::from_f64(value).unwrap_or_default()
+}
+
+/// Vertices of the regular polygon in the plane `z`.
+/// The phase is a rotation of the polygon in the parts of the side.
+fn ring<N>(sides: usize, radius: N, z: N, phase: N) -> impl Iterator<Item = Vec3<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    (0..sides).map(move |j| {
+        let angle = constant::<N>(PI) * constant::<N>(2.) * (constant::<N>(j as f64) + phase)
+            / constant(sides as f64);
+        Vec3::new(radius * angle.cos(), radius * angle.sin(), z)
+    })
+}
+
+/// Build the convex polytope centered at the origin from the vertices and face cycles.
+/// Vertices are scaled to the circumradius and each face is oriented outwards.
+fn convex_from_cycles<N>(vertices: &[[f64; 3]], faces: &[&[usize]], radius: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let vertices: Vec<Vec3<N>> = vertices
+        .iter()
+        .map(|&[x, y, z]| {
+            let vertex = Vec3::new(constant(x), constant(y), constant(z));
+            vertex * (radius / vertex.length())
+        })
+        .collect();
+    oriented_convex(vertices, faces)
+}
+
+/// Build the convex polytope centered at the origin, faces are oriented outwards.
+fn oriented_convex<N>(vertices: Vec<Vec3<N>>, faces: &[&[usize]]) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces = faces
+        .iter()
+        .map(|&face| {
+            let mut face: SmallVec<usize> = face.iter().copied().collect();
+            let (a, b, c) = (vertices[face[0]], vertices[face[1]], vertices[face[2]]);
+            // the polytope is convex and centered, so any face vertex points outwards
+            if (b - a).cross(c - a).dot(a) < N::zero() {
+                face.reverse();
+            }
+            face
+        })
+        .collect();
+
+    Mesh {
+        vertices,
+        faces,
+        ..Default::default()
+    }
+}
+
+/// Build the dual polytope of the convex polytope centered at the origin.
+/// Each face becomes a vertex in the direction of the face center, scaled to the circumradius.
+/// Each vertex becomes a face of its adjacent faces sorted counter-clockwise around the vertex.
+fn dual_of_convex<N>(mesh: &Mesh<N>, radius: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let vertices: Vec<Vec3<N>> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let center = face
+                .iter()
+                .fold(Vec3::zero(), |sum, &index| sum + mesh.vertices[index]);
+            center * (radius / center.length())
+        })
+        .collect();
+
+    let faces = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(vertex_index, &axis)| {
+            // orthonormal basis (u, w, axis) is right-handed,
+            // so the increasing angle is counter-clockwise when viewed from outside
+            let helper = if axis.x.abs() < axis.z.abs() {
+                Vec3::new(N::one(), N::zero(), N::zero())
+            } else {
+                Vec3::new(N::zero(), N::zero(), N::one())
+            };
+            let u = helper.cross(axis);
+            let w = axis.cross(u);
+
+            let mut face: SmallVec<usize> = (0..mesh.faces.len())
+                .filter(|&face_index| mesh.faces[face_index].contains(&vertex_index))
+                .collect();
+            let angle = |face_index: usize| {
+                let v = vertices[face_index];
+                v.dot(w).atan2(v.dot(u))
+            };
+            face.sort_by(|&a, &b| angle(a).partial_cmp(&angle(b)).unwrap());
+            face
+        })
+        .collect();
+
+    Mesh {
+        vertices,
+        faces,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{cantellate::get_faces_normal, mesh::Mesh, vec3::Vec3};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Part of the mesh for the selective cantellation, see `cantellate_selected`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Faces by indices.
+    Faces(Vec<usize>),
+
+    /// Faces of the OBJ group by the name.
+    Group(String),
+
+    /// Faces whose centroid is inside the axis-aligned box.
+    BoundingBox { min: Vec3<N>, max: Vec3<N> },
+
+    /// Faces whose normal deviates from the direction by at most the angle in radians.
+    NormalCone { direction: Vec3<N>, angle: N },
+}
+
+impl<N> Selection<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Flag of each face of the mesh if it's selected.
+    /// Group names of faces are required only for the group selection, see `Mesh::load_obj_groups`.
+    /// The epsilon is a small value to compare floating point numbers.
+    pub fn select(
+        &self,
+        mesh: &Mesh<N>,
+        face_groups: &[String],
+        epsilon: N,
+    ) -> Result<Vec<bool>, String> {
+        let faces_count = mesh.faces.len();
+        match self {
+            Selection::Faces(indices) => {
+                let mut selected = vec![false; faces_count];
+                for &index in indices {
+                    *selected.get_mut(index).ok_or_else(|| {
+                        format!("Selected face {index} is out of range, the mesh has {faces_count} faces")
+                    })? = true;
+                }
+                Ok(selected)
+            }
+            Selection::Group(name) => {
+                if face_groups.len() != faces_count {
+                    return Err("Group selection is supported only for OBJ files".to_owned());
+                }
+                if !face_groups.contains(name) {
+                    let mut names: Vec<&str> = face_groups.iter().map(String::as_str).collect();
+                    names.sort_unstable();
+                    names.dedup();
+                    return Err(format!(
+                        "Group `{name}` is not found, available groups: {}",
+                        names.join(", ")
+                    ));
+                }
+                Ok(face_groups.iter().map(|group| group == name).collect())
+            }
+            Selection::BoundingBox { min, max } => Ok(mesh
+                .faces
+                .iter()
+                .map(|face| {
+                    let centroid = face
+                        .iter()
+                        .filter_map(|&index| mesh.vertices.get(index))
+                        .fold(Vec3::zero(), |sum, &vertex| sum + vertex)
+                        / N::from_usize(face.len().max(1)).unwrap_or_else(N::one);
+                    [
+                        (centroid.x, min.x, max.x),
+                        (centroid.y, min.y, max.y),
+                        (centroid.z, min.z, max.z),
+                    ]
+                    .iter()
+                    .all(|&(value, min, max)| min <= value && value <= max)
+                })
+                .collect()),
+            Selection::NormalCone { direction, angle } => {
+                let direction = direction
+                    .normalize(epsilon)
+                    .ok_or_else(|| "Direction of the normal cone is zero".to_owned())?;
+                let min_cos = angle.cos();
+                Ok(get_faces_normal(mesh, epsilon)
+                    .into_iter()
+                    .map(|normal| normal.is_some_and(|normal| normal.dot(direction) >= min_cos))
+                    .collect())
+            }
+        }
+    }
+
+    /// Faces selected by all selections, all faces are selected if there are no selections.
+    pub fn select_all(
+        selections: &[Self],
+        mesh: &Mesh<N>,
+        face_groups: &[String],
+        epsilon: N,
+    ) -> Result<Vec<bool>, String> {
+        selections
+            .iter()
+            .try_fold(vec![true; mesh.faces.len()], |selected, selection| {
+                let other = selection.select(mesh, face_groups, epsilon)?;
+                Ok(selected.iter().zip(other).map(|(&a, b)| a && b).collect())
+            })
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    cantellate::get_faces_normal,
+    disjoint_set::DisjointSet,
+    mesh::{Mesh, SmallVec},
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use ahash::{AHashMap, AHashSet};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use std::collections::VecDeque;
+
+/// Merge adjacent coplanar faces into single polygons.
+///
+/// The post-process of the cantellation for meshes with coplanar faces, e.g. a triangulated cube:
+/// - edges shorter than the epsilon are collapsed, so zero-width edge strips disappear;
+/// - faces whose normals differ from the normal of the first face of the region by at most the angle
+///   are merged into a single polygon by the boundary of the region;
+/// - vertices which lie on a straight edge between two faces or on a straight boundary are removed.
+///
+/// The angle is an angular tolerance in radians, edges are short by the merge tolerance.
+/// Regions with holes or with self-touching boundaries are kept as is.
+pub fn merge_coplanar_faces<N>(mesh: &Mesh<N>, angle: N, tolerances: &Tolerances<N>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mesh = collapse_short_edges(mesh, tolerances.merge);
+    let mesh = merge_regions(&mesh, angle.cos(), tolerances.face_normal());
+    remove_collinear_vertices(&mesh, angle.sin(), tolerances.normal)
+}
+
+/// Weld vertices closer than the tolerance, e.g. duplicated vertices of the input file
+/// or coincident vertices of the cantellation coming from different source vertices.
+///
+/// Close vertices are found by a spatial hash with the cell size equal to the tolerance,
+/// welding is transitive, so a chain of close vertices is welded into the first vertex of the chain.
+/// Face indices are remapped, repeated consecutive vertices are removed, faces with less than 3 vertices are dropped
+/// and unused vertices are removed. The mesh is returned as is if the tolerance is not positive.
+pub fn weld_vertices<N>(mesh: &Mesh<N>, tolerance: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    weld_vertices_faces(mesh, tolerance).0
+}
+
+/// Same as `weld_vertices`, also returns the index of the source face for each face of the welded mesh.
+pub fn weld_vertices_faces<N>(mesh: &Mesh<N>, tolerance: N) -> (Mesh<N>, Vec<usize>)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    if tolerance <= N::zero() {
+        return (mesh.clone(), (0..mesh.faces.len()).collect());
+    }
+
+    let cell = |vertex: Vec3<N>| -> Option<[i64; 3]> {
+        Some([
+            (vertex.x / tolerance).floor().to_i64()?,
+            (vertex.y / tolerance).floor().to_i64()?,
+            (vertex.z / tolerance).floor().to_i64()?,
+        ])
+    };
+    let mut grid: AHashMap<[i64; 3], SmallVec<usize>> = AHashMap::new();
+    let mut welded = DisjointSet::default();
+    for (index, &vertex) in mesh.vertices.iter().enumerate() {
+        // vertices with non-finite or too far coordinates are not welded
+        let Some([x, y, z]) = cell(vertex) else {
+            continue;
+        };
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(others) = grid.get(&[x + dx, y + dy, z + dz]) else {
+                        continue;
+                    };
+                    for &other in others {
+                        let diff = mesh.vertices[other] - vertex;
+                        if diff.dot(diff) <= tolerance * tolerance {
+                            welded.union(index, other);
+                        }
+                    }
+                }
+            }
+        }
+        grid.entry([x, y, z]).or_default().push(index);
+    }
+
+    let (kept, faces): (Vec<usize>, Vec<SmallVec<usize>>) = mesh
+        .faces
+        .iter()
+        .enumerate()
+        .filter_map(|(face_index, face)| {
+            let mut result = SmallVec::<usize>::new();
+            for &index in face {
+                let index = welded.find(index);
+                if result.last() != Some(&index) {
+                    result.push(index);
+                }
+            }
+            while result.len() > 1 && result.first() == result.last() {
+                result.pop();
+            }
+            (result.len() > 2).then_some((face_index, result))
+        })
+        .unzip();
+    (compact(&mesh.vertices, faces), kept)
+}
+
+/// Weld vertices of face edges shorter than the tolerance and remove faces which become degenerate.
+fn collapse_short_edges<N>(mesh: &Mesh<N>, tolerance: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut welded = DisjointSet::default();
+    for face in &mesh.faces {
+        for i in 0..face.len() {
+            let (a, b) = (face[i], face[(i + 1) % face.len()]);
+            let (Some(&va), Some(&vb)) = (mesh.vertices.get(a), mesh.vertices.get(b)) else {
+                continue;
+            };
+            if (va - vb).dot(va - vb) < tolerance * tolerance {
+                welded.union(a, b);
+            }
+        }
+    }
+
+    let faces = mesh
+        .faces
+        .iter()
+        .filter_map(|face| {
+            let mut result = SmallVec::<usize>::new();
+            for &index in face {
+                let index = welded.find(index);
+                if result.last() != Some(&index) {
+                    result.push(index);
+                }
+            }
+            while result.len() > 1 && result.first() == result.last() {
+                result.pop();
+            }
+            (result.len() > 2).then_some(result)
+        })
+        .collect();
+    compact(&mesh.vertices, faces)
+}
+
+/// Replace each region of coplanar faces with a single polygon.
+fn merge_regions<N>(mesh: &Mesh<N>, min_cos: N, epsilon: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces_normal = get_faces_normal(mesh, epsilon);
+
+    let mut edges: AHashMap<(usize, usize), SmallVec<usize>> = AHashMap::new();
+    for (face_index, face) in mesh.faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let edge = (face[i], face[(i + 1) % face.len()]);
+            edges.entry(edge).or_default().push(face_index);
+        }
+    }
+    // the neighbour face by the manifold edge
+    let neighbour = |a: usize, b: usize| -> Option<usize> {
+        match (
+            edges.get(&(a, b))?.as_slice(),
+            edges.get(&(b, a))?.as_slice(),
+        ) {
+            ([_], [other]) => Some(*other),
+            _ => None,
+        }
+    };
+
+    let mut region_of = vec![None; mesh.faces.len()];
+    let mut faces: Vec<SmallVec<usize>> = Vec::with_capacity(mesh.faces.len());
+    for seed in 0..mesh.faces.len() {
+        if region_of[seed].is_some() {
+            continue;
+        }
+        region_of[seed] = Some(seed);
+        let Some(seed_normal) = faces_normal[seed] else {
+            faces.push(mesh.faces[seed].clone());
+            continue;
+        };
+
+        // grow the region from the seed, each face is compared with the seed to avoid a drift on curved surfaces
+        let mut region = vec![seed];
+        let mut queue = VecDeque::from([seed]);
+        while let Some(face_index) = queue.pop_front() {
+            let face = &mesh.faces[face_index];
+            for i in 0..face.len() {
+                let Some(other) = neighbour(face[i], face[(i + 1) % face.len()]) else {
+                    continue;
+                };
+                let is_coplanar =
+                    faces_normal[other].is_some_and(|normal| normal.dot(seed_normal) >= min_cos);
+                if region_of[other].is_none() && is_coplanar {
+                    region_of[other] = Some(seed);
+                    region.push(other);
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        match region_boundary(mesh, &region) {
+            Some(polygon) if region.len() > 1 => faces.push(polygon),
+            _ => faces.extend(
+                region
+                    .iter()
+                    .map(|&face_index| mesh.faces[face_index].clone()),
+            ),
+        }
+    }
+    compact(&mesh.vertices, faces)
+}
+
+/// Boundary of the region as a single polygon starting from the first vertex of the seed face on the boundary.
+/// Returns `None` if the boundary has several loops or touches itself.
+fn region_boundary<N>(mesh: &Mesh<N>, region: &[usize]) -> Option<SmallVec<usize>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut directed: AHashSet<(usize, usize)> = AHashSet::new();
+    for &face_index in region {
+        let face = &mesh.faces[face_index];
+        for i in 0..face.len() {
+            directed.insert((face[i], face[(i + 1) % face.len()]));
+        }
+    }
+
+    // inner edges go in both directions
+    let mut next: AHashMap<usize, usize> = AHashMap::new();
+    let mut first = None;
+    for &face_index in region {
+        let face = &mesh.faces[face_index];
+        for i in 0..face.len() {
+            let (a, b) = (face[i], face[(i + 1) % face.len()]);
+            if directed.contains(&(b, a)) {
+                continue;
+            }
+            if next.insert(a, b).is_some() {
+                return None;
+            }
+            first.get_or_insert(a);
+        }
+    }
+
+    let first = first?;
+    let mut polygon = SmallVec::new();
+    let mut current = first;
+    loop {
+        polygon.push(current);
+        current = *next.get(&current)?;
+        if current == first {
+            break;
+        }
+        if polygon.len() > next.len() {
+            return None;
+        }
+    }
+    (polygon.len() == next.len()).then_some(polygon)
+}
+
+/// Remove vertices which are used only by faces where they lie on a straight line between the same neighbours.
+fn remove_collinear_vertices<N>(mesh: &Mesh<N>, max_sin: N, epsilon: N) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    // neighbours of the vertex in each face, `None` if it's a corner in one of the faces
+    let mut neighbours: Vec<Option<AHashSet<usize>>> =
+        vec![Some(AHashSet::new()); mesh.vertices.len()];
+    for face in &mesh.faces {
+        for i in 0..face.len() {
+            let prev = face[(i + face.len() - 1) % face.len()];
+            let next = face[(i + 1) % face.len()];
+            let vertex = face[i];
+            let is_straight = is_straight(
+                mesh.vertices[prev],
+                mesh.vertices[vertex],
+                mesh.vertices[next],
+                max_sin,
+                epsilon,
+            );
+            let entry = &mut neighbours[vertex];
+            if let Some(set) = entry.as_mut().filter(|_| is_straight) {
+                set.insert(prev);
+                set.insert(next);
+                if set.len() <= 2 {
+                    continue;
+                }
+            }
+            *entry = None;
+        }
+    }
+
+    let faces = mesh
+        .faces
+        .iter()
+        .filter_map(|face| {
+            let result: SmallVec<usize> = face
+                .iter()
+                .copied()
+                .filter(|&vertex| neighbours[vertex].is_none())
+                .collect();
+            (result.len() > 2).then_some(result)
+        })
+        .collect();
+    compact(&mesh.vertices, faces)
+}
+
+/// The vertex is on the straight segment between its neighbours.
+fn is_straight<N>(prev: Vec3<N>, vertex: Vec3<N>, next: Vec3<N>, max_sin: N, epsilon: N) -> bool
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let (Some(a), Some(b)) = (
+        (vertex - prev).normalize(epsilon),
+        (next - vertex).normalize(epsilon),
+    ) else {
+        return false;
+    };
+    a.dot(b) > N::zero() && a.cross(b).length() <= max_sin
+}
+
+/// Mesh of faces with unused vertices removed, the order of vertices is kept.
+pub(crate) fn compact<N>(vertices: &[Vec3<N>], faces: Vec<SmallVec<usize>>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut remap = vec![None; vertices.len()];
+    let mut used: Vec<usize> = faces.iter().flatten().copied().collect();
+    used.sort_unstable();
+    used.dedup();
+    let mut result = Mesh::default();
+    for index in used {
+        if let Some(&vertex) = vertices.get(index) {
+            remap[index] = Some(result.vertices.len());
+            result.vertices.push(vertex);
+        }
+    }
+    result.faces = faces
+        .iter()
+        .map(|face| {
+            face.iter()
+                .filter_map(|&index| remap.get(index).copied().flatten())
+                .collect()
+        })
+        .collect();
+    result
+}
@@ -0,0 +1,413 @@
+use crate::{
+    cantellate::get_faces_normal, disjoint_set::DisjointSet, hull::is_convex, mesh::Mesh,
+    tolerance::Tolerances, vec3::Vec3,
+};
+use ahash::{AHashMap, AHashSet};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Quality report and statistics of the mesh.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub faces: usize,
+    pub edges: usize,
+
+    /// Count of faces by the count of their vertices.
+    pub face_sizes: BTreeMap<usize, usize>,
+
+    /// Euler characteristic `V - E + F` of the vertices used by faces.
+    pub euler_characteristic: i64,
+
+    /// Total genus of the surface components.
+    /// It's not defined for non-manifold meshes.
+    pub genus: Option<i64>,
+
+    /// Connected components of faces.
+    pub components: usize,
+
+    /// Vertices which are not used by any face.
+    pub isolated_vertices: usize,
+
+    /// Face indices out of the vertex range. Faces with invalid indices are skipped by other statistics
+    /// except for the count of faces and face sizes.
+    pub invalid_indices: usize,
+
+    pub boundary_edges: usize,
+    pub boundary_loops: usize,
+
+    /// Edges with more than two faces.
+    pub non_manifold_edges: usize,
+
+    /// Vertices where adjacent faces form more than one fan.
+    pub non_manifold_vertices: usize,
+
+    pub bounding_box: Option<([f64; 3], [f64; 3])>,
+    pub surface_area: f64,
+
+    /// Signed volume. It's meaningful only for closed meshes.
+    pub volume: f64,
+
+    /// Each face lies on the convex hull of the mesh, see `is_convex`.
+    pub convex: bool,
+
+    pub edge_length: Option<MinAvgMax>,
+
+    /// Distance of n-gon vertices from the plane of the n-gon, triangles are always planar.
+    pub planarity_deviation: Option<MinAvgMax>,
+}
+
+/// Statistics of the input mesh and of the cantellated mesh.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsReport {
+    pub input: MeshStats,
+    pub output: Option<MeshStats>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MinAvgMax {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+impl MinAvgMax {
+    fn from_values(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let (min, max, sum, count) = values.fold(
+            (f64::INFINITY, f64::NEG_INFINITY, 0.0, 0),
+            |(min, max, sum, count), value| {
+                (min.min(value), max.max(value), sum + value, count + 1)
+            },
+        );
+        if count > 0 {
+            Some(Self {
+                min,
+                avg: sum / count as f64,
+                max,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl MeshStats {
+    /// Collect statistics of the mesh.
+    /// Tolerances are used for face normals and the convexity check.
+    /// The mesh is measured in `f64` like values of the report.
+    pub fn new<N>(mesh: &Mesh<N>, tolerances: &Tolerances<N>) -> Self
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let to_f64 = |value: N| value.to_f64().unwrap_or_default();
+        let mesh = Mesh {
+            vertices: mesh
+                .vertices
+                .iter()
+                .map(|v| Vec3::new(to_f64(v.x), to_f64(v.y), to_f64(v.z)))
+                .collect(),
+            faces: mesh.faces.clone(),
+            ..Default::default()
+        };
+        let tolerances = Tolerances {
+            normal: to_f64(tolerances.normal),
+            merge: to_f64(tolerances.merge),
+            planarity: to_f64(tolerances.planarity),
+            angle: to_f64(tolerances.angle),
+            fit_plane: to_f64(tolerances.fit_plane),
+            scale: to_f64(tolerances.scale),
+        };
+        Self::measure(&mesh, &tolerances)
+    }
+
+    fn measure<N>(mesh: &Mesh<N>, tolerances: &Tolerances<N>) -> Self
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let to_f64 = |value: N| value.to_f64().unwrap_or_default();
+        let to_array = |v: Vec3<N>| [to_f64(v.x), to_f64(v.y), to_f64(v.z)];
+
+        let mut face_sizes = BTreeMap::new();
+        for face in &mesh.faces {
+            *face_sizes.entry(face.len()).or_default() += 1;
+        }
+
+        let invalid_indices = mesh
+            .faces
+            .iter()
+            .flatten()
+            .filter(|&&index| index >= mesh.vertices.len())
+            .count();
+        let convex = is_convex(mesh, tolerances);
+        let valid_mesh;
+        let mesh = if invalid_indices > 0 {
+            valid_mesh = Mesh {
+                vertices: mesh.vertices.clone(),
+                faces: mesh
+                    .faces
+                    .iter()
+                    .filter(|face| face.iter().all(|&index| index < mesh.vertices.len()))
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            };
+            &valid_mesh
+        } else {
+            mesh
+        };
+
+        // faces of each undirected edge
+        let mut edges: AHashMap<(usize, usize), usize> = AHashMap::new();
+        for face in &mesh.faces {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+
+        let mut used = vec![false; mesh.vertices.len()];
+        mesh.faces
+            .iter()
+            .flatten()
+            .for_each(|&vertex_index| used[vertex_index] = true);
+        let used_vertices = used.iter().filter(|&&used| used).count();
+
+        // connected components of faces by shared vertices
+        let mut faces_set = DisjointSet::default();
+        for face in &mesh.faces {
+            for &vertex_index in face.iter().skip(1) {
+                faces_set.union(face[0], vertex_index);
+            }
+        }
+        let components = mesh
+            .faces
+            .iter()
+            .filter(|face| !face.is_empty())
+            .map(|face| faces_set.find(face[0]))
+            .collect::<AHashSet<_>>()
+            .len();
+
+        // boundary loops are connected components of boundary edges
+        let mut boundary_set = DisjointSet::default();
+        let mut boundary_vertices = Vec::new();
+        for (&(a, b), _) in edges.iter().filter(|(_, &count)| count == 1) {
+            boundary_set.union(a, b);
+            boundary_vertices.push(a);
+        }
+        let boundary_edges = boundary_vertices.len();
+        let boundary_loops = boundary_vertices
+            .iter()
+            .map(|&vertex_index| boundary_set.find(vertex_index))
+            .collect::<AHashSet<_>>()
+            .len();
+
+        let non_manifold_edges = edges.values().filter(|&&count| count > 2).count();
+        let non_manifold_vertices = count_non_manifold_vertices(mesh);
+
+        let euler_characteristic =
+            used_vertices as i64 - edges.len() as i64 + mesh.faces.len() as i64;
+        // for each orientable component: euler = 2 - 2 * genus - boundary loops
+        let genus_doubled = 2 * components as i64 - euler_characteristic - boundary_loops as i64;
+        let genus = if non_manifold_edges == 0
+            && non_manifold_vertices == 0
+            && components > 0
+            && genus_doubled >= 0
+            && genus_doubled % 2 == 0
+        {
+            Some(genus_doubled / 2)
+        } else {
+            None
+        };
+
+        let bounding_box =
+            mesh.vertices
+                .iter()
+                .fold(None, |bounding_box: Option<([f64; 3], [f64; 3])>, &v| {
+                    let v = to_array(v);
+                    Some(match bounding_box {
+                        None => (v, v),
+                        Some((min, max)) => (
+                            [0, 1, 2].map(|i| f64::min(min[i], v[i])),
+                            [0, 1, 2].map(|i| f64::max(max[i], v[i])),
+                        ),
+                    })
+                });
+
+        let surface_area = (0..mesh.faces.len())
+            .map(|face_index| to_f64(mesh.face_area(face_index)))
+            .fold(0.0, |sum, area| sum + area);
+
+        // sum of signed volumes of tetrahedrons from the origin to the triangle fans of faces
+        let volume = mesh
+            .faces
+            .iter()
+            .map(|face| {
+                (1..face.len().saturating_sub(1))
+                    .map(|i| {
+                        let a = mesh.vertices[face[0]];
+                        let b = mesh.vertices[face[i]];
+                        let c = mesh.vertices[face[i + 1]];
+                        to_f64(a.dot(b.cross(c)))
+                    })
+                    .fold(0.0, |sum, volume| sum + volume)
+            })
+            .fold(0.0, |sum, volume| sum + volume)
+            / 6.0;
+
+        let edge_length = MinAvgMax::from_values(
+            edges
+                .keys()
+                .map(|&(a, b)| to_f64((mesh.vertices[a] - mesh.vertices[b]).length())),
+        );
+
+        let faces_normal = get_faces_normal(mesh, tolerances.face_normal());
+        let planarity_deviation = MinAvgMax::from_values(
+            mesh.faces
+                .iter()
+                .zip(faces_normal)
+                .filter(|(face, _)| face.len() > 3)
+                .filter_map(|(face, normal)| {
+                    let normal = normal?;
+                    let centroid = face
+                        .iter()
+                        .fold(Vec3::zero(), |sum, &index| sum + mesh.vertices[index])
+                        / N::from_usize(face.len())?;
+                    face.iter()
+                        .map(|&index| to_f64((mesh.vertices[index] - centroid).dot(normal).abs()))
+                        .reduce(f64::max)
+                }),
+        );
+
+        Self {
+            vertices: mesh.vertices.len(),
+            faces: face_sizes.values().sum(),
+            edges: edges.len(),
+            face_sizes,
+            euler_characteristic,
+            genus,
+            components,
+            isolated_vertices: mesh.vertices.len() - used_vertices,
+            invalid_indices,
+            boundary_edges,
+            boundary_loops,
+            non_manifold_edges,
+            non_manifold_vertices,
+            bounding_box,
+            surface_area,
+            volume,
+            convex,
+            edge_length,
+            planarity_deviation,
+        }
+    }
+}
+
+/// Count vertices where adjacent faces are not connected into a single fan by the edges from the vertex.
+fn count_non_manifold_vertices<N>(mesh: &Mesh<N>) -> usize
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    // faces of each edge from the vertex, keyed by the vertex and the other end of the edge
+    let mut vertex_edges: AHashMap<(usize, usize), Vec<usize>> = AHashMap::new();
+    let mut vertex_faces: AHashMap<usize, Vec<usize>> = AHashMap::new();
+    for (face_index, face) in mesh.faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let vertex = face[i];
+            let prev = face[(i + face.len() - 1) % face.len()];
+            let next = face[(i + 1) % face.len()];
+            vertex_edges
+                .entry((vertex, prev))
+                .or_default()
+                .push(face_index);
+            vertex_edges
+                .entry((vertex, next))
+                .or_default()
+                .push(face_index);
+            vertex_faces.entry(vertex).or_default().push(face_index);
+        }
+    }
+
+    // faces around the vertex are in the same fan if they share an edge from the vertex
+    let mut fans: AHashMap<usize, DisjointSet> = AHashMap::new();
+    for (&(vertex, _), faces) in &vertex_edges {
+        let fan = fans.entry(vertex).or_default();
+        for &face_index in faces.iter().skip(1) {
+            fan.union(faces[0], face_index);
+        }
+    }
+
+    vertex_faces
+        .iter()
+        .filter(|(vertex, faces)| {
+            let mut fan = fans.remove(vertex).unwrap_or_default();
+            let roots: AHashSet<_> = faces.iter().map(|&face| fan.find(face)).collect();
+            roots.len() > 1
+        })
+        .count()
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+        let min_avg_max = |value: &Option<MinAvgMax>| {
+            optional(value.map(|v| format!("min {:.6}, avg {:.6}, max {:.6}", v.min, v.avg, v.max)))
+        };
+
+        writeln!(
+            f,
+            "  vertices: {} (isolated: {})",
+            self.vertices, self.isolated_vertices
+        )?;
+        writeln!(
+            f,
+            "  faces: {} (invalid indices: {})",
+            self.faces, self.invalid_indices
+        )?;
+        let face_sizes: Vec<_> = self
+            .face_sizes
+            .iter()
+            .map(|(size, count)| format!("{size}: {count}"))
+            .collect();
+        writeln!(f, "  face sizes: {}", face_sizes.join(", "))?;
+        writeln!(f, "  edges: {}", self.edges)?;
+        writeln!(
+            f,
+            "  euler characteristic: {}, genus: {}, components: {}",
+            self.euler_characteristic,
+            optional(self.genus.map(|genus| genus.to_string())),
+            self.components
+        )?;
+        writeln!(
+            f,
+            "  boundary loops: {}, boundary edges: {}",
+            self.boundary_loops, self.boundary_edges
+        )?;
+        writeln!(
+            f,
+            "  non-manifold edges: {}, non-manifold vertices: {}",
+            self.non_manifold_edges, self.non_manifold_vertices
+        )?;
+        writeln!(
+            f,
+            "  bounding box: {}",
+            optional(
+                self.bounding_box
+                    .map(|(min, max)| format!("{min:?} - {max:?}"))
+            )
+        )?;
+        writeln!(
+            f,
+            "  surface area: {:.6}, volume: {:.6}, convex: {}",
+            self.surface_area,
+            self.volume,
+            if self.convex { "yes" } else { "no" }
+        )?;
+        writeln!(f, "  edge length: {}", min_avg_max(&self.edge_length))?;
+        write!(
+            f,
+            "  planarity deviation: {}",
+            min_avg_max(&self.planarity_deviation)
+        )
+    }
+}
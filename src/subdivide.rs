@@ -0,0 +1,256 @@
+use crate::{
+    mesh::{Mesh, SmallVec},
+    vec3::Vec3,
+};
+use ahash::AHashMap;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Subdivision surface scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Scheme {
+    /// Each n-gon is split into n quads around the face point, the surface is smoothed towards the limit surface.
+    /// Boundary edges are kept as cubic B-spline curves.
+    #[default]
+    CatmullClark,
+    /// Each face is shrunk to its corner points, new faces are created for edges and vertices like by the expansion.
+    /// Boundary edges and vertices get no new faces, so an open mesh shrinks.
+    DooSabin,
+}
+
+/// Apply the subdivision scheme the given count of times.
+/// Faces may be arbitrary polygons. Faces with invalid indices or with less than 3 vertices are removed.
+pub fn subdivide<N>(mesh: &Mesh<N>, scheme: Scheme, count: usize) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    (0..count).fold(mesh.clone(), |mesh, _| match scheme {
+        Scheme::CatmullClark => catmull_clark(&mesh),
+        Scheme::DooSabin => doo_sabin(&mesh),
+    })
+}
+
+/// Single step of the Catmull-Clark subdivision.
+///
+/// Vertices of the result are moved original vertices, then edge points, then face points.
+/// Interior vertices use the rule `(F + 2R + (n - 3)P) / n`, boundary vertices with two boundary edges
+/// are smoothed along the boundary and other boundary vertices, including corners of a single face,
+/// stay in place.
+pub fn catmull_clark<N>(mesh: &Mesh<N>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces = valid_faces(mesh);
+    let n = |value: usize| N::from_usize(value).unwrap();
+
+    let face_points: Vec<Vec3<N>> = faces.iter().map(|face| centroid(mesh, face)).collect();
+
+    // faces of each undirected edge
+    let mut edges: AHashMap<(usize, usize), SmallVec<usize>> = AHashMap::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        for (a, b) in face_edges(face) {
+            edges
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push(face_index);
+        }
+    }
+    let mut edge_keys: Vec<(usize, usize)> = edges.keys().copied().collect();
+    edge_keys.sort_unstable();
+
+    // edge points, interior edges are averaged with face points of both faces
+    let mut result = Mesh {
+        vertices: mesh.vertices.clone(),
+        faces: Vec::new(),
+        ..Default::default()
+    };
+    let mut edge_index = AHashMap::with_capacity(edge_keys.len());
+    for &(a, b) in &edge_keys {
+        let middle = (mesh.vertices[a] + mesh.vertices[b]) / n(2);
+        let point = match edges[&(a, b)].as_slice() {
+            &[f, g] => {
+                (mesh.vertices[a] + mesh.vertices[b] + face_points[f] + face_points[g]) / n(4)
+            }
+            _ => middle,
+        };
+        edge_index.insert((a, b), result.vertices.len());
+        result.vertices.push(point);
+    }
+    let face_offset = result.vertices.len();
+    result.vertices.extend(face_points.iter().copied());
+
+    // new positions of original vertices
+    let mut vertex_faces: Vec<SmallVec<usize>> = vec![SmallVec::new(); mesh.vertices.len()];
+    for (face_index, face) in faces.iter().enumerate() {
+        for &vertex in face {
+            vertex_faces[vertex].push(face_index);
+        }
+    }
+    let mut vertex_edges: Vec<SmallVec<(usize, usize)>> =
+        vec![SmallVec::new(); mesh.vertices.len()];
+    for &(a, b) in &edge_keys {
+        vertex_edges[a].push((a, b));
+        vertex_edges[b].push((a, b));
+    }
+    for (vertex, position) in mesh.vertices.iter().enumerate() {
+        let vertex_edges = &vertex_edges[vertex];
+        if vertex_edges.is_empty() {
+            continue;
+        }
+        let other = |&(a, b): &(usize, usize)| if a == vertex { b } else { a };
+        let boundary: SmallVec<usize> = vertex_edges
+            .iter()
+            .filter(|edge| edges[edge].len() != 2)
+            .map(other)
+            .collect();
+        result.vertices[vertex] = match boundary.as_slice() {
+            [] => {
+                let valence = n(vertex_edges.len());
+                let f = vertex_faces[vertex]
+                    .iter()
+                    .fold(Vec3::zero(), |sum, &face| sum + face_points[face])
+                    / n(vertex_faces[vertex].len());
+                let r = vertex_edges.iter().fold(Vec3::zero(), |sum, edge| {
+                    sum + (*position + mesh.vertices[other(edge)]) / n(2)
+                }) / valence;
+                (f + r * n(2) + *position * (valence - n(3))) / valence
+            }
+            &[a, b] if vertex_faces[vertex].len() > 1 => {
+                (*position * n(6) + mesh.vertices[a] + mesh.vertices[b]) / n(8)
+            }
+            _ => *position,
+        };
+    }
+
+    // a quad for each corner of each face
+    let edge_point = |a: usize, b: usize| edge_index[&(a.min(b), a.max(b))];
+    for (face_index, face) in faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let prev = face[(i + face.len() - 1) % face.len()];
+            let vertex = face[i];
+            let next = face[(i + 1) % face.len()];
+            result.faces.push(SmallVec::from_slice(&[
+                vertex,
+                edge_point(vertex, next),
+                face_offset + face_index,
+                edge_point(prev, vertex),
+            ]));
+        }
+    }
+    result
+}
+
+/// Single step of the Doo-Sabin subdivision.
+///
+/// Each corner of a face gets a point which is the average of the vertex, the middles of both corner edges
+/// and the face centroid. Faces keep their corner points, each interior manifold edge gets a quad
+/// and each interior vertex gets a polygon of its corner points.
+pub fn doo_sabin<N>(mesh: &Mesh<N>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces = valid_faces(mesh);
+    let n = |value: usize| N::from_usize(value).unwrap();
+
+    // corner points, the key is the face and the vertex
+    let mut result = Mesh::default();
+    let mut corners: AHashMap<(usize, usize), usize> = AHashMap::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        let face_point = centroid(mesh, face);
+        let mut polygon = SmallVec::new();
+        for i in 0..face.len() {
+            let prev = mesh.vertices[face[(i + face.len() - 1) % face.len()]];
+            let vertex = mesh.vertices[face[i]];
+            let next = mesh.vertices[face[(i + 1) % face.len()]];
+            let point =
+                (vertex + (vertex + prev) / n(2) + (vertex + next) / n(2) + face_point) / n(4);
+            corners.insert((face_index, face[i]), result.vertices.len());
+            polygon.push(result.vertices.len());
+            result.vertices.push(point);
+        }
+        result.faces.push(polygon);
+    }
+
+    // the face of each directed edge, non-manifold edges are skipped
+    let mut directed: AHashMap<(usize, usize), Option<usize>> = AHashMap::new();
+    for (face_index, face) in faces.iter().enumerate() {
+        for edge in face_edges(face) {
+            directed
+                .entry(edge)
+                .and_modify(|face| *face = None)
+                .or_insert(Some(face_index));
+        }
+    }
+    let face_of = |a: usize, b: usize| directed.get(&(a, b)).copied().flatten();
+
+    // quads of interior edges, each edge is taken once in the direction with the smaller first vertex
+    for (face_index, face) in faces.iter().enumerate() {
+        for (a, b) in face_edges(face) {
+            if a > b || face_of(a, b) != Some(face_index) {
+                continue;
+            }
+            let Some(other) = face_of(b, a) else {
+                continue;
+            };
+            result.faces.push(SmallVec::from_slice(&[
+                corners[&(face_index, b)],
+                corners[&(face_index, a)],
+                corners[&(other, a)],
+                corners[&(other, b)],
+            ]));
+        }
+    }
+
+    // polygons of interior vertices, faces around the vertex are walked against the face orientation
+    let mut visited = vec![false; mesh.vertices.len()];
+    for (face_index, face) in faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let vertex = face[i];
+            if std::mem::replace(&mut visited[vertex], true) {
+                continue;
+            }
+            let mut polygon = SmallVec::new();
+            let mut current = face_index;
+            let is_closed = loop {
+                polygon.push(corners[&(current, vertex)]);
+                let current_face = &faces[current];
+                let position = current_face.iter().position(|&v| v == vertex).unwrap();
+                let prev = current_face[(position + current_face.len() - 1) % current_face.len()];
+                match face_of(vertex, prev) {
+                    Some(next) if next == face_index => break true,
+                    Some(next) if polygon.len() < faces.len() => current = next,
+                    _ => break false,
+                }
+            };
+            if is_closed && polygon.len() > 2 {
+                result.faces.push(polygon);
+            }
+        }
+    }
+    result
+}
+
+/// Faces with valid indices and at least 3 vertices.
+fn valid_faces<N>(mesh: &Mesh<N>) -> Vec<SmallVec<usize>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    mesh.faces
+        .iter()
+        .filter(|face| face.len() >= 3 && face.iter().all(|&index| index < mesh.vertices.len()))
+        .cloned()
+        .collect()
+}
+
+/// Directed edges of the face.
+fn face_edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+}
+
+fn centroid<N>(mesh: &Mesh<N>, face: &[usize]) -> Vec3<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    face.iter()
+        .fold(Vec3::zero(), |sum, &index| sum + mesh.vertices[index])
+        / N::from_usize(face.len()).unwrap()
+}
@@ -0,0 +1,99 @@
+use crate::{mesh::Mesh, vec3::Vec3};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Tolerances of the geometric tests, each one is used for quantities of its own kind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerances<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Minimal length of a vector to have a direction, e.g. of the cross product of face edges for the face normal.
+    /// Shorter vectors are degenerate.
+    pub normal: N,
+
+    /// Maximal distance between vertices which are considered the same, e.g. cantellated vertices of neighbour faces.
+    pub merge: N,
+
+    /// Maximal distance of a face vertex from the plane of the face for the face to be planar.
+    pub planarity: N,
+
+    /// Minimal angle in radians between directions which are considered different,
+    /// e.g. of offsets of rounded corners, also the minimal length of a sum of unit vectors.
+    /// It's dimensionless, so it's not scaled by `relative_to`.
+    pub angle: N,
+
+    /// Minimal distance of a face vertex from the plane of the face for the face to take the normal
+    /// of the best-fit plane. It's `FIT_PLANE_RATIO` times the planarity tolerance by default,
+    /// so slightly non-planar faces keep the normal of their corners. Infinity disables fitting.
+    pub fit_plane: N,
+
+    /// Size of the mesh which tolerances are relative to, it's 1 for absolute tolerances.
+    pub scale: N,
+}
+
+impl<N> Tolerances<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    /// Ratio of the default fit plane tolerance to the planarity tolerance.
+    pub const FIT_PLANE_RATIO: f64 = 10.0;
+
+    /// All tolerances are equal to the epsilon, except the fit plane tolerance which is `FIT_PLANE_RATIO` times larger.
+    pub fn new(epsilon: N) -> Self {
+        Self {
+            normal: epsilon,
+            merge: epsilon,
+            planarity: epsilon,
+            angle: epsilon,
+            fit_plane: epsilon * N::from_f64(Self::FIT_PLANE_RATIO).unwrap_or_else(N::one),
+            scale: N::one(),
+        }
+    }
+
+    /// Minimal length of the cross product of face edges for the face normal.
+    /// The cross product grows with the squared size of the mesh, so the normal tolerance is scaled once more.
+    pub fn face_normal(&self) -> N {
+        self.normal * self.scale
+    }
+
+    /// Length tolerances multiplied by the diagonal of the mesh bounding box,
+    /// so values are relative to the size of the mesh and tiny or huge models behave the same.
+    /// The angular tolerance is kept as is.
+    /// Tolerances are kept as is for meshes without vertices or with a zero size.
+    pub fn relative_to(self, mesh: &Mesh<N>) -> Self {
+        let mut vertices = mesh.vertices.iter();
+        let Some(&first) = vertices.next() else {
+            return self;
+        };
+        let (min, max) = vertices.fold((first, first), |(min, max), &v| {
+            (
+                Vec3 {
+                    x: min.x.min(v.x),
+                    y: min.y.min(v.y),
+                    z: min.z.min(v.z),
+                },
+                Vec3 {
+                    x: max.x.max(v.x),
+                    y: max.y.max(v.y),
+                    z: max.z.max(v.z),
+                },
+            )
+        });
+        let diagonal = (max - min).length();
+        if !diagonal.is_normal() {
+            return self;
+        }
+        Self {
+            normal: self.normal * diagonal,
+            merge: self.merge * diagonal,
+            planarity: self.planarity * diagonal,
+            angle: self.angle,
+            fit_plane: if self.fit_plane.is_finite() {
+                self.fit_plane * diagonal
+            } else {
+                self.fit_plane
+            },
+            scale: self.scale * diagonal,
+        }
+    }
+}
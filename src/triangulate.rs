@@ -0,0 +1,237 @@
+use crate::{
+    mesh::{Mesh, SmallVec},
+    normals::FaceNormals,
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Kind of faces of the saved mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFaces {
+    /// Keep polygons as is.
+    #[default]
+    Ngon,
+    /// Split polygons with more than 4 vertices into quads and triangles.
+    Quad,
+    /// Split all polygons into triangles.
+    Tri,
+}
+
+/// Split faces of the mesh into the kind of output faces.
+/// No vertices are added or moved, split faces replace their polygon in place
+/// and take texture coordinates of their corners, so UVs stay valid.
+/// Returns the index of the source face for each face of the result, e.g. to keep groups of faces.
+///
+/// Polygons are split by the ear clipping in the plane of the face normal, see `FaceNormals`,
+/// so non-convex and slightly non-planar polygons are split without overlapping triangles.
+/// Faces with invalid indices are kept as is.
+pub fn split_faces<N>(
+    mesh: &Mesh<N>,
+    output: OutputFaces,
+    tolerances: &Tolerances<N>,
+) -> (Mesh<N>, Vec<usize>)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let max_size = match output {
+        OutputFaces::Ngon => return (mesh.clone(), (0..mesh.faces.len()).collect()),
+        OutputFaces::Quad => 4,
+        OutputFaces::Tri => 3,
+    };
+    let normals = FaceNormals::new(mesh, tolerances).normals;
+
+    let mut result = Mesh {
+        vertices: mesh.vertices.clone(),
+        faces: Vec::with_capacity(mesh.faces.len()),
+        texcoords: mesh.texcoords.clone(),
+        face_texcoords: Vec::new(),
+    };
+    let has_texcoords = !mesh.face_texcoords.is_empty();
+    let mut sources = Vec::with_capacity(mesh.faces.len());
+    for (face_index, face) in mesh.faces.iter().enumerate() {
+        let texcoords = mesh.face_texcoords(face_index).unwrap_or_default();
+        let is_valid = face.iter().all(|&index| index < mesh.vertices.len());
+        let polygons: Vec<SmallVec<usize>> = if face.len() <= max_size || !is_valid {
+            vec![(0..face.len()).collect()]
+        } else {
+            let points = project(mesh, face, normals[face_index]);
+            let triangles = triangulate_polygon(&points);
+            if output == OutputFaces::Quad {
+                pair_triangles(&points, &triangles)
+            } else {
+                triangles
+                    .iter()
+                    .map(|t| t.iter().copied().collect())
+                    .collect()
+            }
+        };
+        // polygons are lists of corners of the source face
+        for polygon in polygons {
+            result
+                .faces
+                .push(polygon.iter().map(|&corner| face[corner]).collect());
+            if has_texcoords {
+                result.face_texcoords.push(
+                    polygon
+                        .iter()
+                        .filter_map(|&corner| texcoords.get(corner).copied())
+                        .collect(),
+                );
+            }
+            sources.push(face_index);
+        }
+    }
+    (result, sources)
+}
+
+/// Coordinates of face vertices in the plane of the normal.
+/// Faces without a normal are projected to the XY plane.
+fn project<N>(mesh: &Mesh<N>, face: &[usize], normal: Option<Vec3<N>>) -> Vec<(N, N)>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let normal = normal.unwrap_or_else(|| Vec3::new(N::zero(), N::zero(), N::one()));
+    let axis = if normal.x.abs() < N::from_f32(0.9).unwrap() {
+        Vec3::new(N::one(), N::zero(), N::zero())
+    } else {
+        Vec3::new(N::zero(), N::one(), N::zero())
+    };
+    let u = normal.cross(axis);
+    let u = u / u.length();
+    let v = normal.cross(u);
+    face.iter()
+        .map(|&index| {
+            let point = mesh.vertices[index];
+            (point.dot(u), point.dot(v))
+        })
+        .collect()
+}
+
+/// Sign of the polygon area, positive for the counter-clockwise order.
+/// Corners whose triangles have the same sign are convex.
+fn orientation<N: Float>(points: &[(N, N)]) -> N {
+    let area = (0..points.len()).fold(N::zero(), |sum, i| {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        sum + a.0 * b.1 - b.0 * a.1
+    });
+    if area < N::zero() {
+        -N::one()
+    } else {
+        N::one()
+    }
+}
+
+/// Doubled signed area of the triangle, positive for the counter-clockwise order.
+fn cross<N: Float>(a: (N, N), b: (N, N), c: (N, N)) -> N {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Split the polygon into triangles of corner indices by the ear clipping.
+/// An ear is a convex corner whose triangle contains no other corner.
+/// If there is no ear because of self-intersections or rounding errors, the most convex corner is clipped,
+/// so the polygon is always split into `n - 2` triangles.
+fn triangulate_polygon<N: Float>(points: &[(N, N)]) -> Vec<[usize; 3]> {
+    let sign = orientation(points);
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + count - 1) % count],
+                remaining[i],
+                remaining[(i + 1) % count],
+            )
+        };
+        let convexity = |i: usize| {
+            let (a, b, c) = corner(i);
+            cross(points[a], points[b], points[c]) * sign
+        };
+        let is_ear = |i: usize| {
+            let (a, b, c) = corner(i);
+            convexity(i) > N::zero()
+                && remaining.iter().all(|&other| {
+                    let p = points[other];
+                    // corners at the same position are not inside, e.g. touching loops
+                    [a, b, c].iter().any(|&index| points[index] == p)
+                        || !is_inside((points[a], points[b], points[c]), p, sign)
+                })
+        };
+
+        let ear = (0..count).find(|&i| is_ear(i)).unwrap_or_else(|| {
+            (0..count)
+                .max_by(|&i, &j| {
+                    convexity(i)
+                        .partial_cmp(&convexity(j))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(0)
+        });
+        let (a, b, c) = corner(ear);
+        triangles.push([a, b, c]);
+        remaining.remove(ear);
+    }
+    if let [a, b, c] = remaining[..] {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+/// The point is inside the triangle or on its edge.
+fn is_inside<N: Float>(triangle: ((N, N), (N, N), (N, N)), p: (N, N), sign: N) -> bool {
+    let (a, b, c) = triangle;
+    cross(a, b, p) * sign >= N::zero()
+        && cross(b, c, p) * sign >= N::zero()
+        && cross(c, a, p) * sign >= N::zero()
+}
+
+/// Join pairs of adjacent triangles into convex quads, triangles without a pair are kept.
+fn pair_triangles<N: Float>(points: &[(N, N)], triangles: &[[usize; 3]]) -> Vec<SmallVec<usize>> {
+    let sign = orientation(points);
+    let is_convex = |quad: &[usize; 4]| {
+        (0..4).all(|i| {
+            let (a, b, c) = (quad[i], quad[(i + 1) % 4], quad[(i + 2) % 4]);
+            cross(points[a], points[b], points[c]) * sign > N::zero()
+        })
+    };
+
+    let mut used = vec![false; triangles.len()];
+    let mut polygons = Vec::with_capacity(triangles.len());
+    for i in 0..triangles.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let quad = (i + 1..triangles.len())
+            .filter(|&j| !used[j])
+            .find_map(|j| {
+                let quad = join_triangles(triangles[i], triangles[j])?;
+                is_convex(&quad).then_some((j, quad))
+            });
+        match quad {
+            Some((j, quad)) => {
+                used[j] = true;
+                polygons.push(quad.iter().copied().collect());
+            }
+            None => polygons.push(triangles[i].iter().copied().collect()),
+        }
+    }
+    polygons
+}
+
+/// Quad of two triangles with a common edge in opposite directions.
+fn join_triangles(first: [usize; 3], second: [usize; 3]) -> Option<[usize; 4]> {
+    for i in 0..3 {
+        let (a, b, c) = (first[i], first[(i + 1) % 3], first[(i + 2) % 3]);
+        for j in 0..3 {
+            let (d, e, f) = (second[j], second[(j + 1) % 3], second[(j + 2) % 3]);
+            // the common edge is `b -> c` in the first triangle and `c -> b` in the second one
+            if d == c && e == b {
+                return Some([a, b, f, c]);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,546 @@
+use crate::{
+    cantellate::{get_faces_normal, FaceKind},
+    disjoint_set::DisjointSet,
+    mesh::{Mesh, SmallVec},
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use ahash::AHashMap;
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+
+/// Original mesh recovered from the cantellated mesh.
+#[derive(Debug, Clone)]
+pub struct Uncantellated<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    pub mesh: Mesh<N>,
+
+    /// Estimated cantellation factor.
+    pub factor: N,
+}
+
+/// Detect edge strips and vertex caps of the cantellated mesh and collapse them back.
+/// Recovers the original mesh and the cantellation factor of the cantellation with faces pushed along normals.
+///
+/// The recognition is based on the topology: each face is adjacent either to edge strips only
+/// or to faces and vertex caps only, so faces are split into two alternating sides.
+/// Edge strips are planar quads, their opposite sides are shared with faces or with vertex caps.
+/// Ambiguous assignments are resolved by the best fit of the factor.
+/// If several assignments fit exactly, the one with vertex caps smaller than faces is chosen,
+/// because vertex caps grow from points with the factor.
+/// Edge strips are planar by the planarity tolerance and factors are compared by the merge tolerance.
+pub fn uncantellate<N>(
+    mesh: &Mesh<N>,
+    tolerances: &Tolerances<N>,
+) -> Result<Uncantellated<N>, String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces_normal = get_faces_normal(mesh, tolerances.face_normal());
+    // thin edge strips between near-coplanar faces have no normal by the tolerance, but are still planar
+    let planes_normal = get_faces_normal(mesh, N::zero());
+    let neighbours = get_faces_neighbours(mesh)?;
+    let (sides, components) = split_faces_by_sides(mesh, &neighbours)?;
+
+    let mut kinds = vec![FaceKind::Face; mesh.faces.len()];
+    let mut total_fit = Fit::default();
+    for component in &components {
+        let mut best: Option<(Vec<FaceKind>, Fit<N>, N)> = None;
+        for edge_side in [false, true] {
+            for vertex_color in [false, true] {
+                let Some(component_kinds) = classify_component(
+                    mesh,
+                    &neighbours,
+                    &planes_normal,
+                    &sides,
+                    component,
+                    edge_side,
+                    vertex_color,
+                    tolerances.planarity,
+                ) else {
+                    continue;
+                };
+                let Some(fit) = fit_component(
+                    mesh,
+                    &neighbours,
+                    &faces_normal,
+                    component,
+                    &component_kinds,
+                ) else {
+                    continue;
+                };
+                // the factor of the wrong assignment is usually negative
+                if fit.factor().is_some_and(|factor| factor < tolerances.merge) {
+                    continue;
+                }
+                // uniform polytopes have several exact assignments, e.g. the cantellated cube
+                // is also the cantellated octahedron, then faces should be larger than vertex caps
+                let caps_ratio = get_caps_ratio(mesh, component, &component_kinds);
+                let is_better = best.as_ref().is_none_or(|(_, best_fit, best_caps_ratio)| {
+                    let difference = fit.residual() - best_fit.residual();
+                    let epsilon = tolerances.merge * tolerances.merge;
+                    difference < -epsilon
+                        || (difference <= epsilon && caps_ratio < *best_caps_ratio)
+                });
+                if is_better {
+                    best = Some((component_kinds, fit, caps_ratio));
+                }
+            }
+        }
+
+        let Some((component_kinds, fit, _)) = best else {
+            return Err(format!(
+                "Mesh is not a cantellation: cannot recognize edge strips of the face {}",
+                component[0]
+            ));
+        };
+        for (&face_index, kind) in component.iter().zip(component_kinds) {
+            kinds[face_index] = kind;
+        }
+        total_fit = total_fit + fit;
+    }
+
+    let factor = total_fit.factor().ok_or_else(|| {
+        "Cannot estimate the cantellation factor: there are no vertex caps".to_owned()
+    })?;
+    let mesh = collapse(mesh, &neighbours, &faces_normal, &kinds, factor);
+    Ok(Uncantellated { mesh, factor })
+}
+
+/// Least squares fit of the factor.
+/// Each original vertex `v` is `c - n * factor` for each of its corners `c` with the face normal `n`.
+/// Sums are accumulated over corners relative to the mean corner and the mean normal of each vertex.
+#[derive(Debug, Clone, Copy, Default)]
+struct Fit<N> {
+    /// Sum of `(c - c_mean) * (n - n_mean)`.
+    numerator: N,
+    /// Sum of `|n - n_mean|^2`.
+    denominator: N,
+    /// Sum of `|c - c_mean|^2`.
+    squares: N,
+}
+
+impl<N> Fit<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    fn factor(&self) -> Option<N> {
+        if self.denominator > N::zero() {
+            Some(self.numerator / self.denominator)
+        } else {
+            None
+        }
+    }
+
+    fn residual(&self) -> N {
+        match self.factor() {
+            Some(factor) => self.squares - self.numerator * factor,
+            None => self.squares,
+        }
+    }
+}
+
+impl<N> std::ops::Add for Fit<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            numerator: self.numerator + other.numerator,
+            denominator: self.denominator + other.denominator,
+            squares: self.squares + other.squares,
+        }
+    }
+}
+
+/// Find the neighbour face by each side of each face.
+/// The side `i` of the face is an edge from the vertex `i` to the next vertex.
+fn get_faces_neighbours<N>(mesh: &Mesh<N>) -> Result<Vec<SmallVec<Option<usize>>>, String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut edges: AHashMap<(usize, usize), usize> = AHashMap::new();
+    for (face_index, face) in mesh.faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let edge = (face[i], face[(i + 1) % face.len()]);
+            if edges.insert(edge, face_index).is_some() {
+                return Err(format!(
+                    "Mesh is not a cantellation: edge {} - {} is not manifold",
+                    edge.0, edge.1
+                ));
+            }
+        }
+    }
+
+    Ok(mesh
+        .faces
+        .iter()
+        .map(|face| {
+            (0..face.len())
+                .map(|i| edges.get(&(face[(i + 1) % face.len()], face[i])).copied())
+                .collect()
+        })
+        .collect())
+}
+
+/// Split faces into two sides so that neighbour faces are on different sides.
+/// Edge strips are on one side, faces and vertex caps are on another.
+/// Returns the side of each face and connected components of faces.
+fn split_faces_by_sides<N>(
+    mesh: &Mesh<N>,
+    neighbours: &[SmallVec<Option<usize>>],
+) -> Result<(Vec<bool>, Vec<Vec<usize>>), String>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut sides: Vec<Option<bool>> = vec![None; mesh.faces.len()];
+    let mut components = Vec::new();
+    for start in 0..mesh.faces.len() {
+        if sides[start].is_some() {
+            continue;
+        }
+
+        sides[start] = Some(false);
+        let mut component = vec![start];
+        let mut i = 0;
+        while i < component.len() {
+            let face_index = component[i];
+            let side = sides[face_index] == Some(true);
+            for &neighbour in neighbours[face_index].iter().flatten() {
+                match sides[neighbour] {
+                    None => {
+                        sides[neighbour] = Some(!side);
+                        component.push(neighbour);
+                    }
+                    Some(neighbour_side) if neighbour_side == side => {
+                        return Err(format!(
+                            "Mesh is not a cantellation: neighbour faces {face_index} and {neighbour} cannot be split into edge strips and other faces"
+                        ));
+                    }
+                    Some(_) => {}
+                }
+            }
+            i += 1;
+        }
+        components.push(component);
+    }
+
+    Ok((
+        sides.into_iter().map(|side| side == Some(true)).collect(),
+        components,
+    ))
+}
+
+/// Assign kinds to faces of the component.
+/// Faces of the edge side are edge strips. Other faces are split into faces and vertex caps:
+/// opposite sides of the edge strip have the same kind and adjacent sides have different kinds.
+/// Returns `None` if the assignment is not consistent.
+#[allow(clippy::too_many_arguments)]
+fn classify_component<N>(
+    mesh: &Mesh<N>,
+    neighbours: &[SmallVec<Option<usize>>],
+    faces_normal: &[Option<Vec3<N>>],
+    sides: &[bool],
+    component: &[usize],
+    edge_side: bool,
+    vertex_color: bool,
+    planarity: N,
+) -> Option<Vec<FaceKind>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    // edge strips are planar quads
+    let edges_valid = component
+        .iter()
+        .filter(|&&face_index| sides[face_index] == edge_side)
+        .all(|&face_index| {
+            mesh.faces[face_index].len() == 4
+                && is_face_planar(mesh, faces_normal, face_index, planarity)
+        });
+    if !edges_valid {
+        return None;
+    }
+
+    // constraints between faces at the sides of edge strips, the flag is true if kinds are the same
+    let mut constraints: AHashMap<usize, SmallVec<(usize, bool)>> = AHashMap::new();
+    for &face_index in component {
+        if sides[face_index] != edge_side {
+            continue;
+        }
+        let around = &neighbours[face_index];
+        for i in 0..4 {
+            for (offset, same) in [(1, false), (2, true)] {
+                if let (Some(a), Some(b)) = (around[i], around[(i + offset) % 4]) {
+                    constraints.entry(a).or_default().push((b, same));
+                    constraints.entry(b).or_default().push((a, same));
+                }
+            }
+        }
+    }
+
+    // color faces by constraints, faces without constraints stay uncolored
+    let mut colors: AHashMap<usize, bool> = AHashMap::new();
+    for &start in component {
+        if sides[start] == edge_side
+            || colors.contains_key(&start)
+            || !constraints.contains_key(&start)
+        {
+            continue;
+        }
+        colors.insert(start, false);
+        let mut stack = vec![start];
+        while let Some(face_index) = stack.pop() {
+            let color = colors[&face_index];
+            for &(other, same) in &constraints[&face_index] {
+                let other_color = color == same;
+                match colors.get(&other) {
+                    None => {
+                        colors.insert(other, other_color);
+                        stack.push(other);
+                    }
+                    Some(&existing) if existing != other_color => return None,
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    Some(
+        component
+            .iter()
+            .map(|face_index| {
+                if sides[*face_index] == edge_side {
+                    FaceKind::Edge
+                } else if colors.get(face_index) == Some(&vertex_color) {
+                    FaceKind::Vertex
+                } else {
+                    FaceKind::Face
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Corners of the original vertex, each corner is paired with the normal of its face.
+type CornersGroup<N> = SmallVec<(usize, Vec3<N>)>;
+
+/// Group corners of faces by original vertices.
+/// Corners are merged along sides of edge strips which are not shared with faces.
+/// Returns `None` if some corner doesn't belong to exactly one face.
+fn group_corners<N>(
+    mesh: &Mesh<N>,
+    neighbours: &[SmallVec<Option<usize>>],
+    faces_normal: &[Option<Vec3<N>>],
+    faces: &[usize],
+    kinds: &[FaceKind],
+) -> Option<Vec<CornersGroup<N>>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    // normal of the face containing the corner
+    let mut corners: AHashMap<usize, Vec3<N>> = AHashMap::new();
+    for (&face_index, &kind) in faces.iter().zip(kinds) {
+        if kind != FaceKind::Face {
+            continue;
+        }
+        let normal = faces_normal[face_index]?;
+        for &vertex_index in &mesh.faces[face_index] {
+            if corners.insert(vertex_index, normal).is_some() {
+                return None;
+            }
+        }
+    }
+
+    let kind_by_face: AHashMap<usize, FaceKind> =
+        faces.iter().copied().zip(kinds.iter().copied()).collect();
+    let is_face = |neighbour: Option<usize>| {
+        neighbour.and_then(|neighbour| kind_by_face.get(&neighbour)) == Some(&FaceKind::Face)
+    };
+
+    let mut disjoint_set = DisjointSet::default();
+    for (&face_index, &kind) in faces.iter().zip(kinds) {
+        let face = &mesh.faces[face_index];
+        match kind {
+            FaceKind::Edge => {
+                let around = &neighbours[face_index];
+                // sides shared with faces are the original edge, other sides are collapsed
+                let collapsed = if is_face(around[0]) || is_face(around[2]) {
+                    [1, 3]
+                } else if is_face(around[1]) || is_face(around[3]) {
+                    [0, 2]
+                } else {
+                    return None;
+                };
+                for i in collapsed {
+                    disjoint_set.union(face[i], face[(i + 1) % 4]);
+                }
+            }
+            FaceKind::Vertex => {
+                for &vertex_index in face.iter().skip(1) {
+                    disjoint_set.union(face[0], vertex_index);
+                }
+            }
+            FaceKind::Face => {}
+        }
+    }
+
+    let mut groups: AHashMap<usize, CornersGroup<N>> = AHashMap::new();
+    let mut roots = Vec::new();
+    for &face_index in faces {
+        for &vertex_index in &mesh.faces[face_index] {
+            let normal = *corners.get(&vertex_index)?;
+            let root = disjoint_set.find(vertex_index);
+            let group = groups.entry(root).or_insert_with(|| {
+                roots.push(root);
+                SmallVec::new()
+            });
+            if group.iter().all(|&(corner, _)| corner != vertex_index) {
+                group.push((vertex_index, normal));
+            }
+        }
+    }
+    Some(
+        roots
+            .into_iter()
+            .map(|root| groups.remove(&root).unwrap_or_default())
+            .collect(),
+    )
+}
+
+/// Fit the factor by the component with the assigned kinds of faces.
+fn fit_component<N>(
+    mesh: &Mesh<N>,
+    neighbours: &[SmallVec<Option<usize>>],
+    faces_normal: &[Option<Vec3<N>>],
+    component: &[usize],
+    kinds: &[FaceKind],
+) -> Option<Fit<N>>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let groups = group_corners(mesh, neighbours, faces_normal, component, kinds)?;
+    let mut fit = Fit::default();
+    for group in groups {
+        let count = N::from_usize(group.len())?;
+        let (corner_sum, normal_sum) = group.iter().fold(
+            (Vec3::zero(), Vec3::zero()),
+            |(corner_sum, normal_sum), &(corner, normal)| {
+                (corner_sum + mesh.vertices[corner], normal_sum + normal)
+            },
+        );
+        let (corner_mean, normal_mean) = (corner_sum / count, normal_sum / count);
+        for &(corner, normal) in &group {
+            let corner = mesh.vertices[corner] - corner_mean;
+            let normal = normal - normal_mean;
+            fit.numerator = fit.numerator + corner.dot(normal);
+            fit.denominator = fit.denominator + normal.dot(normal);
+            fit.squares = fit.squares + corner.dot(corner);
+        }
+    }
+    Some(fit)
+}
+
+/// Build the original mesh: each group of corners becomes a vertex and each face keeps its corners.
+fn collapse<N>(
+    mesh: &Mesh<N>,
+    neighbours: &[SmallVec<Option<usize>>],
+    faces_normal: &[Option<Vec3<N>>],
+    kinds: &[FaceKind],
+    factor: N,
+) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let faces: Vec<usize> = (0..mesh.faces.len()).collect();
+    let groups = group_corners(mesh, neighbours, faces_normal, &faces, kinds).unwrap_or_default();
+
+    let mut result_mesh = Mesh::default();
+    let mut new_index: Vec<Option<usize>> = vec![None; mesh.vertices.len()];
+    for group in &groups {
+        let count = N::from_usize(group.len()).unwrap_or_default();
+        let position = group.iter().fold(Vec3::zero(), |sum, &(corner, normal)| {
+            sum + mesh.vertices[corner] - normal * factor
+        }) / count;
+        for &(corner, _) in group {
+            new_index[corner] = Some(result_mesh.vertices.len());
+        }
+        result_mesh.vertices.push(position);
+    }
+
+    // single points are not cantellated
+    for (vertex_index, vertex) in mesh.vertices.iter().enumerate() {
+        if new_index[vertex_index].is_none()
+            && !mesh.faces.iter().any(|face| face.contains(&vertex_index))
+        {
+            new_index[vertex_index] = Some(result_mesh.vertices.len());
+            result_mesh.vertices.push(*vertex);
+        }
+    }
+
+    for (face, &kind) in mesh.faces.iter().zip(kinds) {
+        if kind != FaceKind::Face {
+            continue;
+        }
+        let mut result_face = SmallVec::<usize>::new();
+        for &vertex_index in face {
+            if let Some(index) = new_index[vertex_index] {
+                if result_face.last() != Some(&index) {
+                    result_face.push(index);
+                }
+            }
+        }
+        if result_face.len() > 1 && result_face.first() == result_face.last() {
+            result_face.pop();
+        }
+        if result_face.len() > 2 {
+            result_mesh.faces.push(result_face);
+        }
+    }
+    result_mesh
+}
+
+/// Ratio of the mean area of vertex caps to the mean area of faces.
+fn get_caps_ratio<N>(mesh: &Mesh<N>, component: &[usize], kinds: &[FaceKind]) -> N
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mean_area = |kind: FaceKind| {
+        let (sum, count) = component
+            .iter()
+            .zip(kinds)
+            .filter(|(_, &face_kind)| face_kind == kind)
+            .fold((N::zero(), 0), |(sum, count), (&face_index, _)| {
+                (sum + mesh.face_area(face_index), count + 1)
+            });
+        sum / N::from_usize(count.max(1)).unwrap_or_default()
+    };
+    let faces_area = mean_area(FaceKind::Face);
+    if faces_area > N::zero() {
+        mean_area(FaceKind::Vertex) / faces_area
+    } else {
+        N::infinity()
+    }
+}
+
+/// Check that all vertices of the face are on the plane of the face.
+/// Faces without a normal are degenerate, e.g. with collinear vertices, and they are planar.
+fn is_face_planar<N>(
+    mesh: &Mesh<N>,
+    faces_normal: &[Option<Vec3<N>>],
+    face_index: usize,
+    planarity: N,
+) -> bool
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let Some(normal) = faces_normal[face_index] else {
+        return true;
+    };
+    let face = &mesh.faces[face_index];
+    let origin = mesh.vertices[face[0]];
+    face.iter()
+        .all(|&vertex_index| (mesh.vertices[vertex_index] - origin).dot(normal).abs() < planarity)
+}
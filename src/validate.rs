@@ -0,0 +1,194 @@
+use crate::mesh::{Mesh, SmallVec};
+use ahash::{AHashMap, AHashSet};
+use num_traits::{float::Float, FromPrimitive, ToPrimitive};
+use serde::Serialize;
+use std::fmt;
+
+/// Problems of the mesh topology which break the cantellation.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// Faces with vertex indices out of the vertices range.
+    pub invalid_indices: Vec<usize>,
+
+    /// Faces with less than 3 vertices or with repeated vertices.
+    pub degenerate_faces: Vec<usize>,
+
+    /// Faces with the same vertices in the same cyclic order as one of the previous faces.
+    /// Faces with the opposite orientation are not duplicates, e.g. two sides of a plane.
+    pub duplicate_faces: Vec<usize>,
+
+    /// Vertices which are not used by any face.
+    pub unused_vertices: Vec<usize>,
+
+    /// Edges with more than two faces.
+    pub non_manifold_edges: Vec<(usize, usize)>,
+
+    /// Edges where both adjacent faces go in the same direction, so face normals are flipped.
+    pub inconsistent_edges: Vec<(usize, usize)>,
+}
+
+impl ValidationReport {
+    pub fn new<N>(mesh: &Mesh<N>) -> Self
+    where
+        N: Float + ToPrimitive + FromPrimitive + Default,
+    {
+        let mut report = Self::default();
+
+        let mut used = vec![false; mesh.vertices.len()];
+        let mut faces_keys: AHashSet<Vec<usize>> = AHashSet::new();
+        let mut directed_edges: AHashMap<(usize, usize), usize> = AHashMap::new();
+        for (face_index, face) in mesh.faces.iter().enumerate() {
+            if face.iter().any(|&index| index >= mesh.vertices.len()) {
+                report.invalid_indices.push(face_index);
+                continue;
+            }
+            face.iter().for_each(|&index| used[index] = true);
+
+            let distinct: AHashSet<_> = face.iter().collect();
+            if face.len() < 3 || distinct.len() != face.len() {
+                report.degenerate_faces.push(face_index);
+                continue;
+            }
+            if !faces_keys.insert(face_key(face)) {
+                report.duplicate_faces.push(face_index);
+                continue;
+            }
+
+            for i in 0..face.len() {
+                let edge = (face[i], face[(i + 1) % face.len()]);
+                *directed_edges.entry(edge).or_default() += 1;
+            }
+        }
+
+        report.unused_vertices = (0..mesh.vertices.len()).filter(|&i| !used[i]).collect();
+
+        let mut edges: AHashMap<(usize, usize), usize> = AHashMap::new();
+        for (&(a, b), &count) in &directed_edges {
+            *edges.entry((a.min(b), a.max(b))).or_default() += count;
+            if count > 1 {
+                report.inconsistent_edges.push((a.min(b), a.max(b)));
+            }
+        }
+        report.non_manifold_edges = edges
+            .into_iter()
+            .filter(|&(_, count)| count > 2)
+            .map(|(edge, _)| edge)
+            .collect();
+
+        report.non_manifold_edges.sort_unstable();
+        report.inconsistent_edges.sort_unstable();
+        report.inconsistent_edges.dedup();
+        report
+    }
+
+    /// Mesh has no problems.
+    pub fn is_valid(&self) -> bool {
+        self.invalid_indices.is_empty()
+            && self.degenerate_faces.is_empty()
+            && self.duplicate_faces.is_empty()
+            && self.unused_vertices.is_empty()
+            && self.non_manifold_edges.is_empty()
+            && self.inconsistent_edges.is_empty()
+    }
+}
+
+/// Repair problems which can be fixed without changing the geometry:
+/// remove faces with invalid indices, repeated consecutive vertices, degenerate and duplicate faces and unused vertices.
+/// Non-manifold and inconsistent edges are kept as is.
+pub fn repair<N>(mesh: &Mesh<N>) -> Mesh<N>
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    repair_faces(mesh).0
+}
+
+/// Same as `repair`, also returns the index of the source face for each face of the repaired mesh.
+pub fn repair_faces<N>(mesh: &Mesh<N>) -> (Mesh<N>, Vec<usize>)
+where
+    N: Float + ToPrimitive + FromPrimitive + Default,
+{
+    let mut faces_keys: AHashSet<Vec<usize>> = AHashSet::new();
+    let (kept, faces): (Vec<usize>, Vec<SmallVec<usize>>) = mesh
+        .faces
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| face.iter().all(|&index| index < mesh.vertices.len()))
+        .map(|(face_index, face)| {
+            let mut face = face.clone();
+            face.dedup();
+            while face.len() > 1 && face.first() == face.last() {
+                face.pop();
+            }
+            (face_index, face)
+        })
+        .filter(|(_, face)| {
+            let distinct: AHashSet<_> = face.iter().collect();
+            face.len() >= 3 && distinct.len() == face.len()
+        })
+        .filter(|(_, face)| faces_keys.insert(face_key(face)))
+        .unzip();
+
+    // remap used vertices to keep their order
+    let mut remap = vec![None; mesh.vertices.len()];
+    let mut result = Mesh::default();
+    for &index in faces.iter().flatten() {
+        if remap[index].is_none() {
+            remap[index] = Some(result.vertices.len());
+            result.vertices.push(mesh.vertices[index]);
+        }
+    }
+    result.faces = faces
+        .iter()
+        .map(|face| face.iter().filter_map(|&index| remap[index]).collect())
+        .collect();
+    (result, kept)
+}
+
+/// Key of the face which is independent of the first vertex, the face is rotated to start from the minimal index.
+/// The winding is kept, so faces with the opposite orientation or other polygons on the same vertices differ.
+fn face_key(face: &[usize]) -> Vec<usize> {
+    let start = (0..face.len()).min_by_key(|&i| face[i]).unwrap_or(0);
+    face[start..]
+        .iter()
+        .chain(&face[..start])
+        .copied()
+        .collect()
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // print only first items of long lists
+        fn list<T: fmt::Debug>(items: &[T]) -> String {
+            const LIMIT: usize = 10;
+            let shown: Vec<_> = items
+                .iter()
+                .take(LIMIT)
+                .map(|item| format!("{item:?}"))
+                .collect();
+            let more = if items.len() > LIMIT { ", ..." } else { "" };
+            format!("{} [{}{more}]", items.len(), shown.join(", "))
+        }
+
+        if self.is_valid() {
+            return write!(f, "  no problems found");
+        }
+        writeln!(
+            f,
+            "  faces with invalid indices: {}",
+            list(&self.invalid_indices)
+        )?;
+        writeln!(f, "  degenerate faces: {}", list(&self.degenerate_faces))?;
+        writeln!(f, "  duplicate faces: {}", list(&self.duplicate_faces))?;
+        writeln!(f, "  unused vertices: {}", list(&self.unused_vertices))?;
+        writeln!(
+            f,
+            "  non-manifold edges: {}",
+            list(&self.non_manifold_edges)
+        )?;
+        write!(
+            f,
+            "  inconsistent edges: {}",
+            list(&self.inconsistent_edges)
+        )
+    }
+}
@@ -125,6 +125,11 @@ where
         )
     }
 
+    #[inline]
+    pub fn dot(self, other: Self) -> N {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
     #[inline]
     pub fn length(self) -> N {
         (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
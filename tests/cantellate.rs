@@ -0,0 +1,155 @@
+//! Placements of cantellated vertices and rounded edge strips.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams, Placement},
+    mesh::Mesh,
+    normals::FaceNormals,
+    primitives::Primitive,
+    stats::MeshStats,
+    tolerance::Tolerances,
+};
+
+const EPSILON: f64 = 1e-6;
+
+/// Convex meshes whose flat cantellation is convex too.
+fn convex_meshes() -> Vec<Mesh<f64>> {
+    vec![
+        Mesh::generate(&Primitive::Cube { size: 1.0 }),
+        Mesh::generate(&Primitive::Icosahedron { radius: 1.0 }),
+    ]
+}
+
+#[test]
+fn rounded_vertices_are_outside_flat_chamfer() {
+    for mesh in convex_meshes() {
+        let flat_params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+        let flat = cantellate(&mesh, &flat_params);
+        let normals = FaceNormals::new(&flat, &flat_params.tolerances).normals;
+        let rounded = cantellate(
+            &mesh,
+            &CantellateParams {
+                segments: 4,
+                ..flat_params
+            },
+        );
+        // the point is inside the convex flat result if it's behind all face planes
+        for &vertex in &rounded.vertices {
+            let distance = flat
+                .faces
+                .iter()
+                .zip(&normals)
+                .map(|(face, normal)| (vertex - flat.vertices[face[0]]).dot(normal.unwrap()))
+                .fold(f64::NEG_INFINITY, f64::max);
+            assert!(distance > -EPSILON, "{vertex:?} is inside the chamfer");
+        }
+    }
+}
+
+#[test]
+fn shrinking_placements_keep_bounding_box() {
+    let tolerances = Tolerances::new(EPSILON);
+    for mesh in convex_meshes() {
+        let (min, max) = MeshStats::new(&mesh, &tolerances).bounding_box.unwrap();
+        for placement in [Placement::Centroid, Placement::Inset] {
+            let params = CantellateParams {
+                placement,
+                ..CantellateParams::new(0.1, tolerances)
+            };
+            let result = (0..3).fold(mesh.clone(), |mesh, _| cantellate(&mesh, &params));
+            let (result_min, result_max) =
+                MeshStats::new(&result, &tolerances).bounding_box.unwrap();
+            for (a, b) in [(min, result_min), (result_max, max)] {
+                assert!(
+                    (0..3).all(|i| a[i] <= b[i] + EPSILON),
+                    "{placement:?} grows the bounding box"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn only_normal_placement_is_rounded() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let flat = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let rounded = CantellateParams {
+        segments: 4,
+        ..flat
+    };
+    assert!(rounded.check().is_ok());
+    for placement in [
+        Placement::Centroid,
+        Placement::Inset,
+        Placement::InsetNormal,
+    ] {
+        let params = CantellateParams {
+            placement,
+            ..rounded
+        };
+        assert!(params.check().is_err());
+        // corners without the common center keep flat strips
+        let result = cantellate(&cube, &params);
+        assert_eq!(
+            result.faces,
+            cantellate(&cube, &CantellateParams { placement, ..flat }).faces
+        );
+    }
+}
+
+#[test]
+fn rounded_mesh_is_watertight_and_keeps_euler_characteristic() {
+    let tolerances = Tolerances::new(EPSILON);
+    let torus = Mesh::<f64>::generate(&Primitive::Torus {
+        major_radius: 1.0,
+        minor_radius: 0.3,
+        major_segments: 12,
+        minor_segments: 8,
+    });
+    for mesh in convex_meshes().into_iter().chain([torus]) {
+        let source = MeshStats::new(&mesh, &tolerances);
+        for segments in [2, 3, 5] {
+            let params = CantellateParams {
+                segments,
+                ..CantellateParams::new(0.05, tolerances)
+            };
+            let stats = MeshStats::new(&cantellate(&mesh, &params), &tolerances);
+            assert_eq!(stats.boundary_edges, 0, "{segments} segments");
+            assert_eq!(stats.non_manifold_edges, 0, "{segments} segments");
+            assert_eq!(stats.non_manifold_vertices, 0, "{segments} segments");
+            assert_eq!(
+                stats.euler_characteristic, source.euler_characteristic,
+                "{segments} segments"
+            );
+        }
+    }
+}
+
+#[test]
+fn rounded_vertices_lie_on_spheres_around_original_vertices() {
+    for mesh in convex_meshes() {
+        let params = CantellateParams {
+            segments: 4,
+            ..CantellateParams::new(0.2, Tolerances::new(EPSILON))
+        };
+        let result = cantellate(&mesh, &params);
+        let flat = cantellate(
+            &mesh,
+            &CantellateParams {
+                segments: 1,
+                ..params
+            },
+        );
+        assert!(result.vertices.len() > flat.vertices.len());
+        for &vertex in &result.vertices {
+            let distance = mesh
+                .vertices
+                .iter()
+                .map(|&original| (vertex - original).length())
+                .fold(f64::INFINITY, f64::min);
+            assert!(
+                (distance - params.factor).abs() < EPSILON,
+                "{vertex:?} is {distance} from the nearest original vertex"
+            );
+        }
+    }
+}
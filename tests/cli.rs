@@ -0,0 +1,528 @@
+//! Runs of the command line tool.
+
+use std::{path::PathBuf, process::Command};
+
+/// Empty temporary directory of the test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cantellation_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_cantellation"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn cantellate(args: &[&str]) -> std::process::Output {
+    run(&[&["cantellate"], args].concat())
+}
+
+/// The run failed with the logged error, not with a panic.
+fn assert_error(output: &std::process::Output, message: &str) {
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(message), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn single_file_is_overwritten_by_second_run() {
+    let dir = temp_dir("overwrite");
+    let output = dir.join("cube.obj");
+    let args = ["-i", "gen:cube", "-o", output.to_str().unwrap()];
+    assert!(cantellate(&args).status.success());
+    let second = cantellate(&args);
+    assert!(second.status.success(), "{second:?}");
+    assert!(output.exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn existing_single_file_is_error_by_policy() {
+    let dir = temp_dir("existing");
+    let output = dir.join("cube.obj");
+    let output = output.to_str().unwrap();
+    assert!(cantellate(&["-i", "gen:cube", "-o", output])
+        .status
+        .success());
+    let second = cantellate(&["-i", "gen:cube", "-o", output, "--on-existing", "error"]);
+    assert_eq!(second.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&second.stderr);
+    assert!(stderr.contains("already exists"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn duplicate_output_paths_are_error() {
+    let dir = temp_dir("duplicates");
+    let input = dir.join("input");
+    std::fs::create_dir_all(input.join("nested")).unwrap();
+    let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+    std::fs::write(input.join("nested/quad.obj"), quad).unwrap();
+    std::fs::write(
+        input.join("nested/quad.off"),
+        "OFF\n4 1 0\n0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n",
+    )
+    .unwrap();
+    let output = dir.join("output");
+    let args = [
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--recursive",
+        "--format",
+        "stl",
+    ];
+    let result = cantellate(&args);
+    assert_eq!(result.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("have the same output file"), "{stderr}");
+    assert!(!output.exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn convert_and_generate_report_file_errors() {
+    let dir = temp_dir("convert_errors");
+    let missing = dir.join("missing.obj");
+    let output = dir.join("cube.obj");
+    let (missing, output) = (missing.to_str().unwrap(), output.to_str().unwrap());
+    assert_error(
+        &run(&["convert", "-i", missing, "-o", output]),
+        "Failed to load",
+    );
+    assert!(!dir.join("cube.obj").exists());
+
+    // the output can't be written under a file
+    std::fs::write(dir.join("file"), "").unwrap();
+    let unwritable = dir.join("file/cube.obj");
+    let unwritable = unwritable.to_str().unwrap();
+    assert_error(
+        &run(&["generate", "cube", "-o", unwritable]),
+        "Failed to save",
+    );
+    assert_error(
+        &run(&["convert", "-i", "gen:cube", "-o", unwritable]),
+        "Failed to save",
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_faces_keep_groups_and_texture_coordinates() {
+    let dir = temp_dir("split_groups");
+    let input = dir.join("hexagon.obj");
+    std::fs::write(
+        &input,
+        "v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\n\
+         vt 0 0\nvt 1 0\nvt 1 0.5\nvt 0.5 0.5\nvt 0.5 1\nvt 0 1\n\
+         g hexagon\nf 1/1 2/2 3/3 4/4 5/5 6/6\n",
+    )
+    .unwrap();
+    let output = dir.join("triangles.obj");
+    let result = run(&[
+        "convert",
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--output-faces",
+        "tri",
+    ]);
+    assert!(result.status.success(), "{result:?}");
+    let text = std::fs::read_to_string(&output).unwrap();
+    assert_eq!(text.matches("\nvt ").count(), 6, "{text}");
+    let faces: Vec<&str> = text.lines().filter(|line| line.starts_with("f ")).collect();
+    assert_eq!(faces.len(), 4, "{text}");
+    assert!(
+        faces.iter().all(|face| face.matches('/').count() == 3),
+        "{text}"
+    );
+    assert!(text.contains("g hexagon"), "{text}");
+
+    // faces split from selected faces are in the `selected` group
+    let output = dir.join("cube.obj");
+    let result = cantellate(&[
+        "-i",
+        "gen:cube",
+        "-o",
+        output.to_str().unwrap(),
+        "--select-faces",
+        "0",
+        "--output-faces",
+        "tri",
+    ]);
+    assert!(result.status.success(), "{result:?}");
+    let text = std::fs::read_to_string(&output).unwrap();
+    assert!(text.contains("g selected"), "{text}");
+    assert!(text.contains("g default"), "{text}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn stats_and_validate_report_errors() {
+    let dir = temp_dir("stats_errors");
+    let missing = dir.join("missing.obj");
+    let missing = missing.to_str().unwrap();
+    assert_error(&run(&["stats", "-i", missing]), "Failed to load");
+    assert_error(&run(&["validate", "-i", missing]), "Failed to load");
+    assert_error(
+        &run(&["stats", "-i", "gen:cube", "--select-faces", "6"]),
+        "out of range",
+    );
+
+    std::fs::write(dir.join("file"), "").unwrap();
+    let unwritable = dir.join("file/cube.obj");
+    assert_error(
+        &run(&[
+            "validate",
+            "-i",
+            "gen:cube",
+            "-r",
+            unwritable.to_str().unwrap(),
+        ]),
+        "Failed to save",
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rounding_requires_normal_placement() {
+    let dir = temp_dir("rounding_placement");
+    let output = dir.join("cube.obj");
+    let output = output.to_str().unwrap();
+    assert_error(
+        &cantellate(&["-i", "gen:cube", "-o", output, "-p", "inset", "-s", "4"]),
+        "require the normal placement",
+    );
+    assert!(cantellate(&["-i", "gen:cube", "-o", output, "-s", "4"])
+        .status
+        .success());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+/// Directory with a single cube mesh to process.
+fn cube_dir(dir: &std::path::Path) -> PathBuf {
+    let input = dir.join("input");
+    let cube = input.join("cube.obj");
+    assert!(run(&["generate", "cube", "-o", cube.to_str().unwrap()])
+        .status
+        .success());
+    input
+}
+
+/// Files which the dry run would write.
+fn planned_outputs(output: &std::process::Output) -> Vec<String> {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("write "))
+        .filter_map(|line| line.split(" from ").next())
+        .map(|path| {
+            let name = std::path::Path::new(path).file_name().unwrap();
+            name.to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn toml_and_json_configs() {
+    let dir = temp_dir("configs");
+    let input = cube_dir(&dir);
+    let output = dir.join("output");
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+    let toml = dir.join("run.toml");
+    std::fs::write(
+        &toml,
+        format!(
+            "input = {input:?}\noutput = {output:?}\noutput-name = \"{{stem}}_{{factor}}_{{count}}.{{ext}}\"\nfactor = 0.5\ncount = 2\n"
+        ),
+    )
+    .unwrap();
+    let result = cantellate(&["--config", toml.to_str().unwrap()]);
+    assert!(result.status.success(), "{result:?}");
+    assert!(dir.join("output/cube_0.5_2.obj").exists());
+
+    let json = dir.join("run.json");
+    std::fs::write(
+        &json,
+        format!(
+            r#"{{"input": {input:?}, "output": {output:?}, "output-name": "{{stem}}_{{segments}}.{{ext}}", "segments": 3, "format": "ply"}}"#
+        ),
+    )
+    .unwrap();
+    let result = cantellate(&["--config", json.to_str().unwrap()]);
+    assert!(result.status.success(), "{result:?}");
+    assert!(dir.join("output/cube_3.ply").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn command_line_overrides_config_and_config_overrides_preset() {
+    let dir = temp_dir("config_order");
+    let input = cube_dir(&dir);
+    let output = dir.join("output");
+    let args = [
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--output-name",
+        "{stem}_{factor}.{ext}",
+        "--dry-run",
+    ];
+    let run_config = |config: &str, extra: &[&str]| {
+        let path = dir.join("run.toml");
+        std::fs::write(&path, config).unwrap();
+        let config_args = ["--config", path.to_str().unwrap()];
+        planned_outputs(&cantellate(&[&args[..], &config_args, extra].concat()))
+    };
+
+    // the game-asset preset has factor 0.02 and OBJ output
+    assert_eq!(
+        planned_outputs(&cantellate(
+            &[&args[..], &["--preset", "game-asset"]].concat()
+        )),
+        ["cube_0.02.obj"]
+    );
+    assert_eq!(
+        run_config("preset = \"game-asset\"\n", &[]),
+        ["cube_0.02.obj"]
+    );
+    assert_eq!(
+        run_config(
+            "preset = \"game-asset\"\nfactor = 0.5\nformat = \"ply\"\n",
+            &[]
+        ),
+        ["cube_0.5.ply"]
+    );
+    assert_eq!(
+        run_config(
+            "preset = \"game-asset\"\nfactor = 0.5\n",
+            &["-f", "0.25", "--format", "stl"]
+        ),
+        ["cube_0.25.stl"]
+    );
+    // the preset of the command line replaces the preset of the file
+    assert_eq!(
+        run_config("preset = \"game-asset\"\n", &["--preset", "print-ready"]),
+        ["cube_0.1.stl"]
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn config_errors() {
+    let dir = temp_dir("config_errors");
+    let config = |name: &str, text: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        cantellate(&["--config", path.to_str().unwrap()])
+    };
+    let missing = dir.join("missing.toml");
+    assert_error(
+        &cantellate(&["--config", missing.to_str().unwrap()]),
+        "Failed to load config",
+    );
+    assert_error(
+        &config("bad.toml", "factor = \"big\"\n"),
+        "Failed to parse config",
+    );
+    assert_error(
+        &config("bad.json", "{\"factor\": 1"),
+        "Failed to parse config",
+    );
+    assert_error(
+        &config("unknown.toml", "size = 1.0\n"),
+        "Failed to parse config",
+    );
+    assert_error(
+        &config("run.yaml", "factor: 1.0\n"),
+        "Unsupported config format",
+    );
+    assert_error(
+        &config("preset.toml", "preset = \"none\"\n"),
+        "Unknown preset",
+    );
+    assert_error(
+        &config(
+            "placement.toml",
+            "input = \"gen:cube\"\nplacement = \"sideways\"\n",
+        ),
+        "Invalid placement in config",
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn single_and_double_precision() {
+    let dir = temp_dir("precision");
+    let output = dir.join("cube.obj");
+    let output = output.to_str().unwrap();
+    for precision in ["f32", "f64"] {
+        let result = cantellate(&[
+            "-i",
+            "gen:cube",
+            "-o",
+            output,
+            "-c",
+            "3",
+            "--precision",
+            precision,
+        ]);
+        assert!(result.status.success(), "{result:?}");
+        assert!(std::path::Path::new(output).exists());
+    }
+    // offsets along diagonal normals are irrational, so there is no exact precision
+    let result = cantellate(&["-i", "gen:cube", "-o", output, "--precision", "exact"]);
+    assert_eq!(result.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("invalid value 'exact'"), "{stderr}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn parallel_jobs_continue_after_bad_input() {
+    let dir = temp_dir("jobs");
+    let input = dir.join("input");
+    std::fs::create_dir_all(&input).unwrap();
+    let quad = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+    for name in ["a.obj", "b.obj", "d.obj"] {
+        std::fs::write(input.join(name), quad).unwrap();
+    }
+    std::fs::write(input.join("c.off"), "OFF\nbroken\n").unwrap();
+    let output = dir.join("output");
+    let result = cantellate(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--jobs",
+        "3",
+    ]);
+    assert_eq!(result.status.code(), Some(1), "{result:?}");
+    for name in ["a.obj", "b.obj", "d.obj"] {
+        assert!(output.join(name).exists(), "{name} is not written");
+    }
+    assert!(!output.join("c.off").exists());
+
+    // the summary has a row of each file in the sorted order
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let rows: Vec<(&str, &str)> = stdout
+        .lines()
+        .filter_map(|line| {
+            let file = line.split_whitespace().next()?;
+            let name = file.strip_prefix(input.to_str().unwrap())?;
+            let status = line.split("  ").filter(|cell| !cell.is_empty()).last()?;
+            Some((name.trim_start_matches('/'), status.trim()))
+        })
+        .collect();
+    assert_eq!(rows.len(), 4, "{stdout}");
+    for (row, name) in rows.iter().zip(["a.obj", "b.obj", "c.off", "d.obj"]) {
+        assert_eq!(row.0, name);
+        if name == "c.off" {
+            assert!(row.1.starts_with("error:"), "{stdout}");
+        } else {
+            assert_eq!(row.1, "ok", "{stdout}");
+        }
+    }
+    assert!(stdout.contains("3 files processed, 1 failed"), "{stdout}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn iterations_are_saved_by_placeholder() {
+    let dir = temp_dir("iterations");
+    let output = dir.join("cube_{iter}.obj");
+    let output = output.to_str().unwrap();
+    let result = cantellate(&["-i", "gen:cube", "-o", output, "-c", "3", "-f", "0.2"]);
+    assert!(result.status.success(), "{result:?}");
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    for iteration in 1..=3 {
+        let path = dir.join(format!("cube_{iteration}.obj"));
+        assert!(path.exists(), "iteration {iteration} is not saved");
+        let stats = format!(
+            "Iteration {iteration} saved to {}; edges count:",
+            path.display()
+        );
+        assert!(stderr.contains(&stats), "{stderr}");
+    }
+    assert_eq!(
+        stderr.matches("euler characteristic: 2,").count(),
+        3,
+        "{stderr}"
+    );
+
+    let every = dir.join("every_{iter}.obj");
+    let result = cantellate(&[
+        "-i",
+        "gen:cube",
+        "-o",
+        every.to_str().unwrap(),
+        "-c",
+        "5",
+        "-f",
+        "0.2",
+        "--save-every",
+        "2",
+    ]);
+    assert!(result.status.success(), "{result:?}");
+    // each second iteration and the last one
+    for iteration in 1..=5 {
+        let path = dir.join(format!("every_{iteration}.obj"));
+        assert_eq!(path.exists(), [2, 4, 5].contains(&iteration), "{iteration}");
+    }
+
+    let plain = dir.join("cube.obj");
+    assert_error(
+        &cantellate(&[
+            "-i",
+            "gen:cube",
+            "-o",
+            plain.to_str().unwrap(),
+            "--save-every",
+            "2",
+        ]),
+        "must contain {iter}",
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn clean_directory_with_iterations_in_output_names() {
+    let dir = temp_dir("clean_iterations");
+    let input = cube_dir(&dir);
+    let output = dir.join("output");
+    std::fs::create_dir_all(&output).unwrap();
+    let stale = output.join("stale.obj");
+    std::fs::write(&stale, "").unwrap();
+
+    let result = cantellate(&[
+        "-i",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--output-name",
+        "{stem}_{iter}.obj",
+        "-c",
+        "2",
+        "--save-every",
+        "1",
+        "--on-existing",
+        "clean",
+    ]);
+    assert!(result.status.success(), "{result:?}");
+    assert!(!stale.exists(), "the output directory is not cleaned");
+    for iteration in 1..=2 {
+        let path = output.join(format!("cube_{iteration}.obj"));
+        assert!(path.exists(), "iteration {iteration} is not saved");
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
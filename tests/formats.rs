@@ -0,0 +1,112 @@
+//! Saving and loading of mesh file formats.
+
+use cantellation::{
+    mesh::Mesh, primitives::Primitive, stats::MeshStats, tolerance::Tolerances, vec3::Vec3,
+};
+use std::path::PathBuf;
+
+/// Temporary file of the test.
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cantellation_{}_{name}", std::process::id()))
+}
+
+/// Save and load the mesh by the file extension.
+fn round_trip(mesh: &Mesh<f64>, name: &str) -> Mesh<f64> {
+    let path = temp_file(name);
+    mesh.save(&path).unwrap();
+    let loaded = Mesh::load(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    loaded
+}
+
+#[test]
+fn ply_round_trip() {
+    let torus = Mesh::<f64>::generate(&Primitive::Torus {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+        major_segments: 12,
+        minor_segments: 8,
+    });
+    let loaded = round_trip(&torus, "torus.ply");
+    assert_eq!(loaded.vertices, torus.vertices);
+    assert_eq!(loaded.faces, torus.faces);
+}
+
+#[test]
+fn ply_face_with_many_vertices() {
+    // the count of vertices doesn't fit a byte
+    let polygon = Mesh::<f64> {
+        vertices: (0..300)
+            .map(|i| {
+                let angle = i as f64 * std::f64::consts::TAU / 300.0;
+                Vec3::new(angle.cos(), angle.sin(), 0.0)
+            })
+            .collect(),
+        faces: vec![(0..300).collect()],
+        ..Default::default()
+    };
+    let path = temp_file("polygon.ply");
+    polygon.save(&path).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.contains("property list uint uint vertex_indices"));
+    let loaded = Mesh::<f64>::load(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.faces, polygon.faces);
+}
+
+#[test]
+fn stl_round_trip() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let loaded = round_trip(&cube, "cube.stl");
+    // quads are split into triangles, equal vertices are merged
+    assert_eq!(loaded.vertices.len(), 8);
+    assert_eq!(loaded.faces.len(), 12);
+    let tolerances = Tolerances::new(1e-6);
+    let stats = MeshStats::new(&loaded, &tolerances);
+    assert_eq!(stats.boundary_edges, 0);
+    assert!((stats.volume - MeshStats::new(&cube, &tolerances).volume).abs() < 1e-6);
+    for vertex in &loaded.vertices {
+        assert!(cube.vertices.iter().any(|v| (*v - *vertex).length() < 1e-6));
+    }
+}
+
+#[test]
+fn stl_with_invalid_indices_is_error() {
+    let mut cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    cube.faces.push([0, 1, 99].into_iter().collect());
+    let path = temp_file("invalid.stl");
+    assert!(cube.save_stl(&path).is_err());
+    assert!(!path.exists());
+}
+
+/// Load the text as a mesh file by the extension of the name.
+fn load_text(text: &str, name: &str) -> Result<Mesh<f64>, String> {
+    let path = temp_file(name);
+    std::fs::write(&path, text).unwrap();
+    let loaded = Mesh::load(&path);
+    std::fs::remove_file(path).unwrap();
+    loaded
+}
+
+#[test]
+fn off_with_negative_or_fractional_indices_is_error() {
+    let vertices = "0 0 0\n1 0 0\n0 1 0\n";
+    assert!(load_text(&format!("OFF\n3 1 0\n{vertices}3 0 1 2\n"), "valid.off").is_ok());
+    for face in ["3 0 1 -1", "3 0 1 1.5", "3.0 0 1 2", "-3 0 1 2"] {
+        let text = format!("OFF\n3 1 0\n{vertices}{face}\n");
+        let error = load_text(&text, "invalid.off").unwrap_err();
+        assert!(error.contains("invalid face"), "{face}: {error}");
+    }
+    let error = load_text(&format!("OFF\n-3 1 0\n{vertices}"), "count.off").unwrap_err();
+    assert!(error.contains("invalid vertices count"), "{error}");
+}
+
+#[test]
+fn ply_with_negative_or_fractional_indices_is_error() {
+    let header = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n";
+    assert!(load_text(&format!("{header}3 0 1 2\n"), "valid.ply").is_ok());
+    for face in ["3 0 1 -1", "3 0 1 1.5", "3.0 0 1 2", "-3 0 1 2"] {
+        let error = load_text(&format!("{header}{face}\n"), "invalid.ply").unwrap_err();
+        assert!(error.contains("invalid value"), "{face}: {error}");
+    }
+}
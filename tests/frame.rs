@@ -0,0 +1,64 @@
+//! Lattice of edge strips and vertex caps with the wall thickness.
+
+use cantellation::{
+    cantellate::{cantellate_selected, CantellateParams, FaceKind},
+    frame::{frame, frame_cantellated, thicken},
+    mesh::Mesh,
+    primitives::Primitive,
+    stats::MeshStats,
+    tolerance::Tolerances,
+};
+
+const EPSILON: f64 = 1e-6;
+
+#[test]
+fn cantellated_face_kinds() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let cantellated = cantellate_selected(&cube, &params, &[true; 6]);
+    let count = |kind| cantellated.kinds.iter().filter(|&&k| k == kind).count();
+    assert_eq!(cantellated.kinds.len(), cantellated.mesh.faces.len());
+    assert_eq!(count(FaceKind::Vertex), 8);
+    assert_eq!(count(FaceKind::Edge), 12);
+    assert_eq!(count(FaceKind::Face), 6);
+}
+
+#[test]
+fn cube_frame_is_closed_lattice() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let result = frame(&cube, &params, 0.05);
+    let stats = MeshStats::new(&result, &Tolerances::new(EPSILON));
+    // a sphere with 6 holes thickened into a solid
+    assert_eq!(stats.boundary_edges, 0);
+    assert_eq!(stats.non_manifold_edges, 0);
+    assert_eq!(stats.euler_characteristic, -8);
+    assert_eq!(stats.genus, Some(5));
+    assert!(stats.volume > 0.0);
+}
+
+#[test]
+fn frame_keeps_unselected_faces() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let mut selected = [false; 6];
+    selected[0] = true;
+    let cantellated = cantellate_selected(&cube, &params, &selected);
+    let result = frame_cantellated(&cantellated, 0.05, &params.tolerances);
+    // a single hole makes a thick cup
+    let stats = MeshStats::new(&result, &Tolerances::new(EPSILON));
+    assert_eq!(stats.boundary_edges, 0);
+    assert_eq!(stats.genus, Some(0));
+    assert!(stats.volume > 0.0);
+}
+
+#[test]
+fn thicken_open_quad() {
+    let quad = Mesh::<f64>::parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+    let result = thicken(&quad, 0.1, &Tolerances::new(EPSILON));
+    assert_eq!(result.vertices.len(), 8);
+    assert_eq!(result.faces.len(), 6);
+    let stats = MeshStats::new(&result, &Tolerances::new(EPSILON));
+    assert_eq!(stats.boundary_edges, 0);
+    assert!((stats.volume - 0.1).abs() < EPSILON);
+}
@@ -0,0 +1,145 @@
+//! Quickhull convex hull, convexity check and hull-based cantellation.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    hull::{cantellate_hull, convex_hull, is_convex},
+    mesh::Mesh,
+    primitives::Primitive,
+    stats::MeshStats,
+    tolerance::Tolerances,
+    validate::ValidationReport,
+    vec3::Vec3,
+};
+
+const EPSILON: f64 = 1e-6;
+
+#[test]
+fn hull_of_cube_with_inner_points() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let mut points = cube.vertices.clone();
+    points.extend([
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.1, -0.2, 0.3),
+        // on the face and on the edge
+        Vec3::new(0.5, 0.1, 0.2),
+        Vec3::new(0.5, 0.5, 0.0),
+    ]);
+    let hull = convex_hull(&points, &Tolerances::new(EPSILON)).unwrap();
+    assert_eq!(hull.vertices.len(), 8);
+    assert_eq!(hull.faces.len(), 6);
+    assert!(hull.faces.iter().all(|face| face.len() == 4));
+    let stats = MeshStats::new(&hull, &Tolerances::new(EPSILON));
+    assert!((stats.volume - 1.0).abs() < EPSILON);
+    assert!(stats.convex);
+}
+
+#[test]
+fn hull_contains_all_points() {
+    // points of the golden spiral on the sphere and inside it
+    let points: Vec<Vec3<f64>> = (0..200)
+        .map(|i| {
+            let t = (i as f64 + 0.5) / 200.0;
+            let z = 1.0 - 2.0 * t;
+            let r = (1.0 - z * z).sqrt() * if i % 3 == 0 { 0.5 } else { 1.0 };
+            let angle = i as f64 * 2.399_963;
+            Vec3::new(r * angle.cos(), r * angle.sin(), z)
+        })
+        .collect();
+    let tolerances = Tolerances::new(EPSILON);
+    let hull = convex_hull(&points, &tolerances).unwrap();
+    assert!(ValidationReport::new(&hull).is_valid());
+    let stats = MeshStats::new(&hull, &Tolerances::new(EPSILON));
+    assert_eq!(stats.euler_characteristic, 2);
+    assert_eq!(stats.boundary_edges, 0);
+    assert!(stats.volume > 0.0);
+    assert!(is_convex(&hull, &tolerances));
+
+    // adding the hull faces back doesn't grow the hull
+    let again = convex_hull(&hull.vertices, &tolerances).unwrap();
+    assert_eq!(again.vertices.len(), hull.vertices.len());
+    assert!(
+        (MeshStats::new(&again, &Tolerances::new(EPSILON)).volume - stats.volume).abs() < EPSILON
+    );
+}
+
+#[test]
+fn coplanar_points_have_no_hull() {
+    let points = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+    assert!(convex_hull(&points, &Tolerances::new(EPSILON)).is_err());
+    assert!(convex_hull::<f64>(&[], &Tolerances::new(EPSILON)).is_err());
+}
+
+#[test]
+fn convexity_of_meshes() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let tolerances = Tolerances::new(EPSILON);
+    let icosahedron = Mesh::<f64>::generate(&Primitive::Icosahedron { radius: 1.0 });
+    assert!(is_convex(&icosahedron, &tolerances));
+    assert!(is_convex(&cantellate(&icosahedron, &params), &tolerances));
+    let torus = Mesh::<f64>::generate(&Primitive::Torus {
+        major_radius: 1.0,
+        minor_radius: 0.3,
+        major_segments: 12,
+        minor_segments: 8,
+    });
+    assert!(!is_convex(&torus, &tolerances));
+}
+
+#[test]
+fn hull_cantellation_of_cube() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let hull = cantellate_hull(&cube, &params).unwrap();
+    // rhombicuboctahedron: 8 triangles and 18 squares
+    assert_eq!(hull.vertices.len(), 24);
+    assert_eq!(hull.faces.len(), 26);
+    let expected = MeshStats::new(&cantellate(&cube, &params), &Tolerances::new(EPSILON)).volume;
+    assert!((MeshStats::new(&hull, &Tolerances::new(EPSILON)).volume - expected).abs() < EPSILON);
+}
+
+#[test]
+fn hull_of_small_rounded_cantellation() {
+    // tiny triangles of rounded caps must keep their planes
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let params = CantellateParams {
+        segments: 3,
+        ..CantellateParams::new(0.01, Tolerances::new(1e-3))
+    };
+    let hull = cantellate_hull(&cube, &params).unwrap();
+    assert!(is_convex(&hull, &params.tolerances));
+    let stats = MeshStats::new(&hull, &Tolerances::new(EPSILON));
+    assert_eq!(stats.euler_characteristic, 2);
+    assert_eq!(stats.boundary_edges, 0);
+    let (min, max) = stats.bounding_box.unwrap();
+    for i in 0..3 {
+        assert!((max[i] - 0.51).abs() < 1e-3 && (min[i] + 0.51).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn hull_of_large_model_with_relative_tolerances() {
+    let sphere = |radius| {
+        Mesh::<f64>::generate(&Primitive::UvSphere {
+            radius,
+            segments: 12,
+            rings: 6,
+        })
+    };
+    let unit = sphere(1.0);
+    let expected = convex_hull(&unit.vertices, &Tolerances::new(1e-4)).unwrap();
+    // quads of the sphere are planar, neighbour faces differ by 30 degrees and are not merged
+    assert_eq!(expected.faces.len(), unit.faces.len());
+    assert_eq!(expected.vertices.len(), unit.vertices.len());
+
+    let large = sphere(1000.0);
+    let tolerances = Tolerances::new(1e-4).relative_to(&large);
+    let hull = convex_hull(&large.vertices, &tolerances).unwrap();
+    assert_eq!(hull.faces.len(), expected.faces.len());
+    assert_eq!(hull.vertices.len(), expected.vertices.len());
+    assert!(is_convex(&hull, &tolerances));
+}
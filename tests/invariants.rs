@@ -0,0 +1,292 @@
+//! Property-based tests of topological invariants of the cantellation
+//! on random convex polytopes and random closed manifold meshes.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    hull::convex_hull,
+    mesh::Mesh,
+    primitives::Primitive,
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+use proptest::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+
+/// Random rotation, scale along axes and translation. It keeps faces planar and convex polytopes convex.
+fn transform(mesh: Mesh<f64>, scale: [f64; 3], angles: [f64; 3], shift: [f64; 3]) -> Mesh<f64> {
+    let rotate = |v: Vec3<f64>, angle: f64, axis: usize| {
+        let (sin, cos) = angle.sin_cos();
+        let mut p = [v.x, v.y, v.z];
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        (p[a], p[b]) = (p[a] * cos - p[b] * sin, p[a] * sin + p[b] * cos);
+        Vec3::new(p[0], p[1], p[2])
+    };
+    Mesh {
+        vertices: mesh
+            .vertices
+            .iter()
+            .map(|&v| {
+                let v = Vec3::new(v.x * scale[0], v.y * scale[1], v.z * scale[2]);
+                let v = (0..3).fold(v, |v, axis| rotate(v, angles[axis], axis));
+                v + Vec3::new(shift[0], shift[1], shift[2])
+            })
+            .collect(),
+        faces: mesh.faces,
+        ..Default::default()
+    }
+}
+
+fn affine() -> impl Strategy<Value = ([f64; 3], [f64; 3], [f64; 3])> {
+    (
+        [0.5..2.0, 0.5..2.0, 0.5..2.0],
+        [0.0..6.3, 0.0..6.3, 0.0..6.3],
+        [-5.0..5.0, -5.0..5.0, -5.0..5.0],
+    )
+}
+
+/// Convex primitives under a random affine transform and convex hulls of random points.
+fn convex_polytope() -> impl Strategy<Value = Mesh<f64>> {
+    let primitive = prop_oneof![
+        (0.5..3.0).prop_map(|radius| Primitive::Tetrahedron { radius }),
+        (0.5..3.0).prop_map(|size| Primitive::Cube { size }),
+        (0.5..3.0).prop_map(|radius| Primitive::Octahedron { radius }),
+        (0.5..3.0).prop_map(|radius| Primitive::Dodecahedron { radius }),
+        (0.5..3.0).prop_map(|radius| Primitive::Icosahedron { radius }),
+        (3usize..12, 0.5..3.0, 0.5..3.0).prop_map(|(sides, radius, height)| Primitive::Prism {
+            sides,
+            radius,
+            height
+        }),
+        (3usize..12, 0.5..3.0, 0.5..3.0).prop_map(|(sides, radius, height)| Primitive::Antiprism {
+            sides,
+            radius,
+            height
+        }),
+        (3usize..12, 0.5..3.0, 0.5..3.0).prop_map(|(sides, radius, height)| Primitive::Pyramid {
+            sides,
+            radius,
+            height
+        }),
+        (0.5..3.0, 3usize..10, 2usize..8).prop_map(|(radius, segments, rings)| {
+            Primitive::UvSphere {
+                radius,
+                segments,
+                rings,
+            }
+        }),
+        (0.5..3.0, 0usize..3).prop_map(|(radius, subdivisions)| Primitive::Icosphere {
+            radius,
+            subdivisions
+        }),
+    ];
+    let primitive = (primitive, affine()).prop_map(|(primitive, (scale, angles, shift))| {
+        transform(Mesh::generate(&primitive), scale, angles, shift)
+    });
+    let hull = prop::collection::vec([-1.0..1.0, -1.0..1.0, -1.0..1.0], 4..40).prop_filter_map(
+        "coplanar points",
+        |points| {
+            let points: Vec<Vec3<f64>> =
+                points.iter().map(|&[x, y, z]| Vec3::new(x, y, z)).collect();
+            convex_hull(&points, &Tolerances::new(1e-6)).ok()
+        },
+    );
+    prop_oneof![primitive, hull]
+}
+
+/// Non-convex closed manifolds: icospheres with random vertex radii and tori of genus 1.
+fn manifold_mesh() -> impl Strategy<Value = Mesh<f64>> {
+    let icosphere = (0usize..3)
+        .prop_flat_map(|subdivisions| {
+            let mesh = Mesh::<f64>::generate(&Primitive::Icosphere {
+                radius: 1.0,
+                subdivisions,
+            });
+            let count = mesh.vertices.len();
+            (Just(mesh), prop::collection::vec(-0.02..0.02, count))
+        })
+        .prop_map(|(mut mesh, jitter)| {
+            for (vertex, jitter) in mesh.vertices.iter_mut().zip(jitter) {
+                *vertex = *vertex * (1.0 + jitter);
+            }
+            mesh
+        });
+    // tori with `minor_segments % 4 == 2` have horizontal bands of coplanar neighbour faces
+    let torus = (1.5..3.0, 0.2..1.0, 3usize..16, 3usize..10, affine()).prop_map(
+        |(major_radius, minor_radius, major_segments, minor_segments, (scale, angles, shift))| {
+            let torus = Mesh::generate(&Primitive::Torus {
+                major_radius,
+                minor_radius,
+                major_segments,
+                minor_segments,
+            });
+            transform(torus, scale, angles, shift)
+        },
+    );
+    prop_oneof![icosphere, torus]
+}
+
+/// Some faces with a common edge have normals closer than the tolerance, so the cantellation may merge them.
+fn has_coplanar_neighbours(mesh: &Mesh<f64>, tolerance: f64) -> bool {
+    let normals: Vec<Vec3<f64>> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[face[i]]);
+            let normal = (b - a).cross(c - a);
+            normal / normal.length()
+        })
+        .collect();
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (face_index, face) in mesh.faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let (a, b) = (face[i], face[(i + 1) % face.len()]);
+            edge_faces
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push(face_index);
+        }
+    }
+    edge_faces.values().any(|faces| {
+        faces.len() == 2 && (normals[faces[0]] - normals[faces[1]]).length() < tolerance
+    })
+}
+
+/// Undirected edges with counts of their faces.
+fn edges(mesh: &Mesh<f64>) -> HashMap<(usize, usize), usize> {
+    let mut edges = HashMap::new();
+    for face in &mesh.faces {
+        for i in 0..face.len() {
+            let (a, b) = (face[i], face[(i + 1) % face.len()]);
+            *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    edges
+}
+
+fn euler_characteristic(mesh: &Mesh<f64>) -> i64 {
+    mesh.vertices.len() as i64 - edges(mesh).len() as i64 + mesh.faces.len() as i64
+}
+
+/// Each directed edge is used once and the opposite edge exists.
+fn assert_watertight(mesh: &Mesh<f64>) -> Result<(), TestCaseError> {
+    let mut directed = HashMap::new();
+    for face in &mesh.faces {
+        for i in 0..face.len() {
+            *directed
+                .entry((face[i], face[(i + 1) % face.len()]))
+                .or_insert(0) += 1;
+        }
+    }
+    for (&(a, b), &count) in &directed {
+        prop_assert_eq!(count, 1, "edge {} - {} is used {} times", a, b, count);
+        prop_assert!(
+            directed.contains_key(&(b, a)),
+            "edge {} - {} is on the boundary",
+            a,
+            b
+        );
+    }
+    Ok(())
+}
+
+fn assert_invariants(mesh: &Mesh<f64>, result: &Mesh<f64>) -> Result<(), TestCaseError> {
+    assert_watertight(mesh)?;
+    let edges_count = edges(mesh).len();
+
+    // coplanar neighbour faces are cantellated as one face without the edge strip between them,
+    // so only the topology of the surface is kept and counts are not larger than without merging
+    if has_coplanar_neighbours(mesh, 1e-2) {
+        prop_assert!(result.vertices.len() <= 2 * edges_count);
+        prop_assert!(result.faces.len() <= mesh.faces.len() + edges_count + mesh.vertices.len());
+        prop_assert_eq!(euler_characteristic(result), euler_characteristic(mesh));
+        return assert_watertight(result);
+    }
+
+    let mut degrees = vec![0; mesh.vertices.len()];
+    for (a, b) in edges(mesh).into_keys() {
+        degrees[a] += 1;
+        degrees[b] += 1;
+    }
+
+    // each vertex is split into vertices by its edges
+    prop_assert_eq!(result.vertices.len(), degrees.iter().sum::<usize>());
+    // new faces are created for each edge and each vertex
+    prop_assert_eq!(
+        result.faces.len(),
+        mesh.faces.len() + edges_count + mesh.vertices.len()
+    );
+    prop_assert_eq!(euler_characteristic(result), euler_characteristic(mesh));
+    assert_watertight(result)?;
+
+    // faces keep their sizes, vertex caps have sizes of vertex degrees and each edge face is a quad
+    let mut expected_sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    for face in &mesh.faces {
+        *expected_sizes.entry(face.len()).or_default() += 1;
+    }
+    for &degree in &degrees {
+        *expected_sizes.entry(degree).or_default() += 1;
+    }
+    *expected_sizes.entry(4).or_default() += edges_count;
+    let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    for face in &result.faces {
+        *sizes.entry(face.len()).or_default() += 1;
+    }
+    prop_assert_eq!(sizes, expected_sizes);
+    Ok(())
+}
+
+/// Faces as cycles of vertex positions starting from the smallest position.
+fn faces_by_positions(mesh: &Mesh<f64>) -> Vec<Vec<[u64; 3]>> {
+    let mut faces: Vec<Vec<[u64; 3]>> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let mut positions: Vec<_> = face
+                .iter()
+                .map(|&i| {
+                    let v = mesh.vertices[i];
+                    [v.x, v.y, v.z].map(f64::to_bits)
+                })
+                .collect();
+            let start = (0..positions.len())
+                .min_by_key(|&i| positions[i])
+                .unwrap_or(0);
+            positions.rotate_left(start);
+            positions
+        })
+        .collect();
+    faces.sort();
+    faces
+}
+
+proptest! {
+    #[test]
+    fn convex_polytope_invariants(mesh in convex_polytope(), factor in 0.05..2.0) {
+        let result = cantellate(&mesh, &CantellateParams::new(factor, Tolerances::new(1e-6)));
+        assert_invariants(&mesh, &result)?;
+    }
+
+    #[test]
+    fn manifold_mesh_invariants(mesh in manifold_mesh(), factor in 0.01..0.5) {
+        let result = cantellate(&mesh, &CantellateParams::new(factor, Tolerances::new(1e-6)));
+        assert_invariants(&mesh, &result)?;
+    }
+
+    #[test]
+    fn smooth_edges_keep_topology(mesh in prop_oneof![convex_polytope(), manifold_mesh()], factor in 0.01..0.5) {
+        // all edges are smoother than the straight angle, so vertices are moved without splitting
+        let params = CantellateParams { min_angle: std::f64::consts::PI, ..CantellateParams::new(factor, Tolerances::new(1e-6)) };
+        let result = cantellate(&mesh, &params);
+        prop_assert_eq!(result.vertices.len(), mesh.vertices.len());
+        prop_assert_eq!(result.faces.len(), mesh.faces.len());
+        prop_assert_eq!(euler_characteristic(&result), euler_characteristic(&mesh));
+        assert_watertight(&result)?;
+    }
+
+    #[test]
+    fn zero_factor_collapses_to_original(mesh in prop_oneof![convex_polytope(), manifold_mesh()]) {
+        let result = cantellate(&mesh, &CantellateParams::new(0.0, Tolerances::new(1e-6)));
+        prop_assert_eq!(result.vertices.len(), mesh.vertices.len());
+        prop_assert_eq!(faces_by_positions(&result), faces_by_positions(&mesh));
+    }
+}
@@ -0,0 +1,134 @@
+//! Regression tests of malformed input found by fuzzing, see `fuzz/`.
+//! Loading and cantellation must return an error or a mesh instead of a panic.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams, Placement},
+    mesh::Mesh,
+    primitives::Primitive,
+    stats::MeshStats,
+    tolerance::Tolerances,
+    vec3::Vec3,
+};
+
+const PLACEMENTS: [Placement; 4] = [
+    Placement::Normal,
+    Placement::Centroid,
+    Placement::Inset,
+    Placement::InsetNormal,
+];
+
+/// Cantellate the mesh with all placements, with rounding and with smooth edges, all result indices must be valid.
+fn cantellate_all(mesh: &Mesh<f64>) {
+    for placement in PLACEMENTS {
+        for (segments, min_angle) in [(1, 0.0), (3, 0.0), (1, 0.5), (3, 0.5)] {
+            let params = CantellateParams {
+                factor: 0.1,
+                tolerances: Tolerances::new(1e-6),
+                placement,
+                inset: 0.1,
+                segments,
+                min_angle,
+            };
+            let result = cantellate(mesh, &params);
+            assert!(
+                result
+                    .faces
+                    .iter()
+                    .flatten()
+                    .all(|&index| index < result.vertices.len()),
+                "invalid result indices for {placement:?} with {segments} segments and min angle {min_angle}"
+            );
+        }
+    }
+}
+
+#[test]
+fn obj_relative_index_overflow() {
+    let text = "v 0 0 0\nv 1 0 0\nf -9223372036854775808 1 2\n";
+    assert!(Mesh::<f64>::parse_obj(text).is_err());
+}
+
+#[test]
+fn obj_relative_index_before_first_vertex() {
+    let text = "v 0 0 0\nv 1 0 0\nf -3 1 2\n";
+    assert!(Mesh::<f64>::parse_obj(text).is_err());
+}
+
+#[test]
+fn obj_zero_index() {
+    let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n";
+    assert!(Mesh::<f64>::parse_obj(text).is_err());
+}
+
+#[test]
+fn obj_unicode_whitespace_before_statement() {
+    let text = "\u{3000}o name\n\u{3000}g group\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 -1\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.faces.len(), 1);
+    assert_eq!(mesh.faces[0].as_slice(), &[0, 1, 2]);
+}
+
+#[test]
+fn obj_unknown_statements_are_ignored() {
+    let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvp 0.5 0.5\ncstype bezier\nf 1 2 3 # face\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    assert_eq!(mesh.faces.len(), 1);
+    assert_eq!(mesh.faces[0].as_slice(), &[0, 1, 2]);
+}
+
+#[test]
+fn obj_invalid_numbers() {
+    for text in ["v 0 0\n", "v 0 0 x\n", "f 1/2/3 x\n", "f /1 2 3\n"] {
+        assert!(Mesh::<f64>::parse_obj(text).is_err(), "{text:?}");
+    }
+}
+
+#[test]
+fn cantellate_out_of_range_indices() {
+    let mut mesh = Mesh::<f64>::generate(&Primitive::Tetrahedron { radius: 1.0 });
+    // the face shares an edge with the valid face
+    let (a, b) = (mesh.faces[0][1], mesh.faces[0][0]);
+    mesh.faces.push([a, b, 99].into_iter().collect());
+    mesh.faces.push([100, 101, 102].into_iter().collect());
+    cantellate_all(&mesh);
+}
+
+#[test]
+fn stats_of_out_of_range_indices() {
+    let tetrahedron = Mesh::<f64>::generate(&Primitive::Tetrahedron { radius: 1.0 });
+    let mut mesh = tetrahedron.clone();
+    mesh.faces.push([0, 1, 99].into_iter().collect());
+    mesh.faces.push([100, 101, 102].into_iter().collect());
+    let tolerances = Tolerances::new(1e-6);
+    let stats = MeshStats::new(&mesh, &tolerances);
+    let expected = MeshStats::new(&tetrahedron, &tolerances);
+    assert_eq!(stats.invalid_indices, 4);
+    assert_eq!(stats.faces, 6);
+    // faces with invalid indices are skipped
+    assert_eq!(stats.edges, expected.edges);
+    assert_eq!(stats.genus, Some(0));
+    assert!((stats.volume - expected.volume).abs() < 1e-9);
+    assert!(!stats.convex);
+}
+
+#[test]
+fn cantellate_degenerate_faces() {
+    let mut mesh = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    mesh.faces.push(Default::default());
+    mesh.faces.push([0].into_iter().collect());
+    mesh.faces.push([0, 1].into_iter().collect());
+    mesh.faces.push([0, 1, 0].into_iter().collect());
+    // duplicate of the face
+    mesh.faces.push(mesh.faces[0].clone());
+    cantellate_all(&mesh);
+}
+
+#[test]
+fn cantellate_non_finite_vertices() {
+    for value in [f64::NAN, f64::INFINITY, f64::MAX] {
+        let mut mesh = Mesh::<f64>::generate(&Primitive::Octahedron { radius: 1.0 });
+        mesh.vertices[0] = Vec3::new(value, 0.0, -value);
+        cantellate_all(&mesh);
+    }
+}
@@ -0,0 +1,83 @@
+//! Fallback normals of degenerate and non-planar faces.
+
+use cantellation::{
+    cantellate::{cantellate_selected, CantellateParams},
+    mesh::Mesh,
+    normals::FaceNormals,
+    tolerance::Tolerances,
+};
+
+#[test]
+fn degenerate_face_takes_normal_of_neighbours() {
+    let params = CantellateParams::new(0.1, Tolerances::new(1e-6));
+    // the second face is a sliver with collinear vertices on the edge of the flat quad
+    let text = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nv 1 0 0\nf 1 5 2 3 4\nf 1 2 5\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let normals = FaceNormals::new(&mesh, &Tolerances::new(1e-6));
+    assert_eq!(normals.report.fallback, [1]);
+    assert!(normals.report.unresolved.is_empty());
+    let normal = normals.normals[1].unwrap();
+    assert!((normal.z - 1.0).abs() < 1e-9);
+
+    // corners of the sliver are pushed together with the quad instead of staying in place
+    let result = cantellate_selected(&mesh, &params, &[true, true]);
+    assert_eq!(result.normals, normals.report);
+    assert!(result
+        .mesh
+        .vertices
+        .iter()
+        .all(|v| (v.z - 0.1).abs() < 1e-9));
+}
+
+#[test]
+fn isolated_degenerate_face_is_reported() {
+    let text = "v 0 0 0\nv 1 0 0\nv 2 0 0\nf 1 2 3\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let normals = FaceNormals::new(&mesh, &Tolerances::new(1e-6));
+    assert!(normals.report.fallback.is_empty());
+    assert_eq!(normals.report.unresolved, [0]);
+    assert_eq!(normals.normals, [None]);
+}
+
+#[test]
+fn non_planar_face_uses_best_fit_plane() {
+    // the quad is folded along the diagonal, two opposite vertices are lifted
+    let text = "v -1 -1 0\nv 1 -1 0.5\nv 1 1 0\nv -1 1 0.5\nf 1 2 3 4\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let tolerances = Tolerances::new(1e-3);
+    let normals = FaceNormals::new(&mesh, &tolerances);
+    assert_eq!(normals.report.non_planar, [0]);
+    let normal = normals.normals[0].unwrap();
+    assert!(normal.x.abs() < 1e-9 && normal.y.abs() < 1e-9 && normal.z > 0.0);
+
+    // best-fit planes are disabled by the infinite tolerance
+    let disabled = Tolerances {
+        fit_plane: f64::INFINITY,
+        ..tolerances
+    };
+    assert!(FaceNormals::new(&mesh, &disabled).report.is_empty());
+
+    // the planar quad and the quad folded within the fit plane tolerance are not reported
+    let flat = Mesh::<f64>::parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+    assert!(FaceNormals::new(&flat, &tolerances).report.is_empty());
+    let slightly = "v -1 -1 0\nv 1 -1 0.005\nv 1 1 0\nv -1 1 0.005\nf 1 2 3 4\n";
+    let slightly = Mesh::<f64>::parse_obj(slightly).unwrap();
+    assert!(FaceNormals::new(&slightly, &tolerances).report.is_empty());
+}
+
+#[test]
+fn non_planar_face_is_pushed_along_best_fit_normal_by_default() {
+    let text = "v -1 -1 0\nv 1 -1 0.5\nv 1 1 0\nv -1 1 0.5\nf 1 2 3 4\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let result = cantellate_selected(
+        &mesh,
+        &CantellateParams::new(0.1, Tolerances::new(1e-3)),
+        &[true],
+    );
+    assert_eq!(result.normals.non_planar, [0]);
+    // all corners are moved by the same offset, so the face keeps its shape
+    for (source, moved) in mesh.vertices.iter().zip(&result.mesh.vertices) {
+        let offset = *moved - *source;
+        assert!(offset.x.abs() < 1e-9 && offset.y.abs() < 1e-9 && (offset.z - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,65 @@
+//! Built-in primitives and parsing of their specs.
+
+use cantellation::{mesh::Mesh, primitives::Primitive, stats::MeshStats, tolerance::Tolerances};
+
+/// Counts of vertices and faces of the primitive by the spec.
+fn counts(spec: &str) -> (usize, usize) {
+    let mesh = Mesh::<f64>::generate(&spec.parse::<Primitive>().unwrap());
+    (mesh.vertices.len(), mesh.faces.len())
+}
+
+#[test]
+fn primitive_counts() {
+    for (spec, expected) in [
+        ("tetrahedron", (4, 4)),
+        ("cube", (8, 6)),
+        ("octahedron", (6, 8)),
+        ("dodecahedron", (20, 12)),
+        ("icosahedron", (12, 20)),
+        ("prism:sides=5", (10, 7)),
+        ("antiprism:sides=5", (10, 12)),
+        ("pyramid:sides=5", (6, 6)),
+        ("uv_sphere:segments=8,rings=4", (8 * 3 + 2, 8 * 4)),
+        ("icosphere:subdivisions=1", (42, 80)),
+        ("torus:major_segments=8,minor_segments=6", (48, 48)),
+        ("grid:x_segments=3,y_segments=2", (12, 6)),
+    ] {
+        assert_eq!(counts(spec), expected, "{spec}");
+    }
+}
+
+#[test]
+fn closed_primitives_are_valid_solids() {
+    let tolerances = Tolerances::new(1e-9);
+    for &name in Primitive::NAMES.iter().filter(|&&name| name != "grid") {
+        let mesh = Mesh::<f64>::generate(&name.parse::<Primitive>().unwrap());
+        let stats = MeshStats::new(&mesh, &tolerances);
+        assert_eq!(stats.boundary_edges, 0, "{name}");
+        assert!(stats.volume > 0.0, "{name}");
+    }
+}
+
+#[test]
+fn invalid_params_are_rejected() {
+    for spec in [
+        "cube:size=0",
+        "cube:size=-1",
+        "cube:size=NaN",
+        "cube:size=inf",
+        "tetrahedron:radius=x",
+        "prism:height=0",
+        "pyramid:radius=-2",
+        "uv_sphere:segments=2",
+        "uv_sphere:rings=1",
+        "icosphere:subdivisions=-1",
+        "torus:minor_radius=-1",
+        "torus:major_radius=0.1",
+        "torus:major_segments=0",
+        "grid:width=inf",
+        "grid:x_segments=0",
+        "cube:radius=1",
+        "sphere",
+    ] {
+        assert!(spec.parse::<Primitive>().is_err(), "{spec}");
+    }
+}
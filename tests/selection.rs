@@ -0,0 +1,79 @@
+//! Selective cantellation of a part of the mesh.
+
+use cantellation::{
+    cantellate::{cantellate, cantellate_selected, CantellateParams},
+    mesh::Mesh,
+    primitives::Primitive,
+    selection::Selection,
+    tolerance::Tolerances,
+    validate::ValidationReport,
+    vec3::Vec3,
+};
+
+const EPSILON: f64 = 1e-6;
+
+#[test]
+fn all_selected_matches_cantellate() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let mesh = Mesh::<f64>::generate(&Primitive::Icosahedron { radius: 1.0 });
+    let selected = cantellate_selected(&mesh, &params, &vec![true; mesh.faces.len()]);
+    let all = cantellate(&mesh, &params);
+    assert_eq!(selected.mesh.vertices, all.vertices);
+    assert_eq!(selected.mesh.faces, all.faces);
+    assert!(selected.selected.iter().all(|&s| s));
+}
+
+#[test]
+fn single_face_gets_transition_strips() {
+    let params = CantellateParams::new(0.2, Tolerances::new(EPSILON));
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let selected = Selection::NormalCone {
+        direction: Vec3::from([0.0, 0.0, 1.0]),
+        angle: 0.1,
+    }
+    .select(&cube, &[], EPSILON)
+    .unwrap();
+    assert_eq!(selected.iter().filter(|&&s| s).count(), 1);
+
+    let result = cantellate_selected(&cube, &params, &selected);
+    // 6 faces and 4 strips along edges of the selected face, no vertex caps
+    assert_eq!(result.mesh.faces.len(), 10);
+    assert_eq!(result.mesh.vertices.len(), 12);
+    assert!(ValidationReport::new(&result.mesh).is_valid());
+    assert_eq!(result.selected.iter().filter(|&&s| s).count(), 1);
+
+    // the selection follows the cantellated face in the next iteration
+    let next = cantellate_selected(&result.mesh, &params, &result.selected);
+    assert!(ValidationReport::new(&next.mesh).is_valid());
+}
+
+#[test]
+fn group_and_box_selections() {
+    let text = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\ng top\nf 1 3 4\n";
+    let (mesh, groups) = Mesh::<f64>::parse_obj_groups(text).unwrap();
+    assert_eq!(groups, ["default", "top"]);
+    assert_eq!(
+        Selection::Group("top".to_owned())
+            .select(&mesh, &groups, EPSILON)
+            .unwrap(),
+        [false, true]
+    );
+    assert!(Selection::Group("bottom".to_owned())
+        .select(&mesh, &groups, EPSILON)
+        .is_err());
+    assert!(Selection::Faces(vec![2])
+        .select(&mesh, &groups, EPSILON)
+        .is_err());
+
+    let selections = [
+        Selection::Faces(vec![0, 1]),
+        Selection::BoundingBox {
+            min: Vec3::from([0.5, 0.0, -1.0]),
+            max: Vec3::from([1.0, 0.5, 1.0]),
+        },
+    ];
+    assert_eq!(
+        Selection::select_all(&selections, &mesh, &groups, EPSILON).unwrap(),
+        [true, false]
+    );
+}
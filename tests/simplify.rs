@@ -0,0 +1,113 @@
+//! Merging of coplanar faces after the cantellation.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    mesh::Mesh,
+    primitives::Primitive,
+    simplify::{merge_coplanar_faces, weld_vertices},
+    tolerance::Tolerances,
+    validate::ValidationReport,
+    vec3::Vec3,
+};
+use std::{collections::BTreeMap, path::Path};
+
+const EPSILON: f64 = 1e-6;
+
+/// Cantellate and merge coplanar faces, the angular tolerance is 1 degree.
+fn cantellate_merged(mesh: &Mesh<f64>, iterations: usize) -> Mesh<f64> {
+    let params = CantellateParams::new(0.5, Tolerances::new(EPSILON));
+    (0..iterations).fold(mesh.clone(), |mesh, _| {
+        merge_coplanar_faces(
+            &cantellate(&mesh, &params),
+            1f64.to_radians(),
+            &Tolerances::new(EPSILON),
+        )
+    })
+}
+
+/// Counts of vertices, edges and faces and the histogram of face sizes.
+fn topology(mesh: &Mesh<f64>) -> (usize, usize, usize, BTreeMap<usize, usize>) {
+    let mut edges: Vec<(usize, usize)> = mesh
+        .faces
+        .iter()
+        .flat_map(|face| {
+            (0..face.len()).map(|i| {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                (a.min(b), a.max(b))
+            })
+        })
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    let mut sizes = BTreeMap::new();
+    for face in &mesh.faces {
+        *sizes.entry(face.len()).or_default() += 1;
+    }
+    (mesh.vertices.len(), edges.len(), mesh.faces.len(), sizes)
+}
+
+#[test]
+fn triangulated_cube_matches_cube() {
+    let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+    let cube = Mesh::<f64>::load_obj(assets.join("cube.obj")).unwrap();
+    let triangulated = Mesh::<f64>::load_obj(assets.join("triangulated_cube.obj")).unwrap();
+    for iterations in 1..=3 {
+        assert_eq!(
+            topology(&cantellate_merged(&triangulated, iterations)),
+            topology(&cantellate_merged(&cube, iterations)),
+            "iteration {iterations}"
+        );
+    }
+}
+
+#[test]
+fn no_coplanar_faces_are_kept() {
+    let params = CantellateParams::new(0.5, Tolerances::new(EPSILON));
+    let icosahedron = Mesh::<f64>::generate(&Primitive::Icosahedron { radius: 1.0 });
+    let cantellated = cantellate(&icosahedron, &params);
+    let merged = merge_coplanar_faces(&cantellated, 1f64.to_radians(), &Tolerances::new(EPSILON));
+    assert_eq!(merged.vertices, cantellated.vertices);
+    assert_eq!(merged.faces, cantellated.faces);
+}
+
+#[test]
+fn flat_grid_becomes_single_face() {
+    let grid =
+        Mesh::<f64>::load_obj(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/plane_mesh.obj"))
+            .unwrap();
+    let merged = merge_coplanar_faces(&grid, 1f64.to_radians(), &Tolerances::new(EPSILON));
+    assert_eq!(merged.faces.len(), 1);
+    assert_eq!(merged.faces[0].len(), 4);
+}
+
+#[test]
+fn triangle_soup_is_welded() {
+    // each face of the cube has its own vertices, shifted by less than the tolerance
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    let mut soup = Mesh::<f64>::default();
+    for (face_index, face) in cube.faces.iter().enumerate() {
+        let shift = Vec3::from([1e-5, -1e-5, 1e-5]) * face_index as f64;
+        let start = soup.vertices.len();
+        soup.vertices
+            .extend(face.iter().map(|&index| cube.vertices[index] + shift));
+        soup.faces.push((start..start + face.len()).collect());
+    }
+
+    let welded = weld_vertices(&soup, 1e-3);
+    assert_eq!(topology(&welded), topology(&cube));
+    assert!(ValidationReport::new(&welded).is_valid());
+
+    // the tolerance is smaller than shifts, nothing is welded
+    let kept = weld_vertices(&soup, 1e-6);
+    assert_eq!(kept.vertices, soup.vertices);
+    assert_eq!(kept.faces, soup.faces);
+}
+
+#[test]
+fn collapsed_faces_are_removed() {
+    let text = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 1 1.0001 0\nv 0 1 0\nf 1 2 3 5\nf 2 4 3\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let welded = weld_vertices(&mesh, 1e-3);
+    assert_eq!(welded.vertices.len(), 4);
+    assert_eq!(welded.faces.len(), 1);
+}
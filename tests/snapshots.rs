@@ -0,0 +1,296 @@
+//! Snapshot tests: cantellate every mesh of `assets` and compare with `results/iteration_{1,2,3}`.
+//! Stored results are made without best-fit normals of non-planar faces. Results with default tolerances,
+//! which fit normals, are stored in `results/fit_plane` only where they differ from `results`.
+//! Meshes are compared by vertex positions and faces, the order of vertices and faces doesn't matter.
+//! Run with `UPDATE_SNAPSHOTS=1` to overwrite stored results after an intended algorithm change.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    mesh::Mesh,
+    tolerance::Tolerances,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+const ITERATIONS: usize = 3;
+
+const EPSILON: f64 = 0.001;
+
+/// Vertices closer than this distance are the same.
+const TOLERANCE: f64 = 1e-4;
+
+/// Count of differences printed for each mesh.
+const REPORT_LIMIT: usize = 5;
+
+#[test]
+fn snapshots() {
+    let tolerances = Tolerances {
+        fit_plane: f64::INFINITY,
+        ..Tolerances::new(EPSILON)
+    };
+    check_snapshots(&tolerances, None);
+}
+
+#[test]
+fn fit_plane_snapshots() {
+    check_snapshots(&Tolerances::new(EPSILON), Some("fit_plane"));
+}
+
+/// Cantellate assets and compare iterations with stored results.
+/// Results of the `overrides` subdirectory replace results of `results`, they are stored only if they differ.
+fn check_snapshots(tolerances: &Tolerances<f64>, overrides: Option<&str>) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let params = CantellateParams::new(1.0, *tolerances);
+
+    let mut assets: Vec<PathBuf> = std::fs::read_dir(root.join("assets"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("obj"))
+        .collect();
+    assets.sort();
+    assert!(!assets.is_empty(), "no assets found");
+
+    let mut failures = String::new();
+    let mut compared = 0;
+    for asset in &assets {
+        let name = asset.file_name().unwrap();
+        let stored_path = |directory: &Path, iteration: usize| {
+            directory.join(format!("iteration_{iteration}")).join(name)
+        };
+        let base_path = |iteration: usize| stored_path(&root.join("results"), iteration);
+        let override_path = |iteration: usize| {
+            overrides.map(|o| stored_path(&root.join("results").join(o), iteration))
+        };
+        let expected_path = |iteration: usize| {
+            override_path(iteration)
+                .filter(|path| path.exists())
+                .unwrap_or_else(|| base_path(iteration))
+        };
+        // not every asset has all iterations stored, skip iterations after the last stored one
+        let iterations = if update {
+            ITERATIONS
+        } else {
+            (1..=ITERATIONS)
+                .filter(|&iteration| expected_path(iteration).exists())
+                .max()
+                .unwrap_or(0)
+        };
+
+        let mut mesh = Mesh::<f64>::load_obj(asset).unwrap();
+        for iteration in 1..=iterations {
+            mesh = cantellate(&mesh, &params);
+
+            if update {
+                let path = match override_path(iteration) {
+                    // the override is kept only if the base one is stored and differs from the result
+                    Some(path) => {
+                        let base = base_path(iteration);
+                        let same = !base.exists()
+                            || compare(&Mesh::<f64>::load_obj(&base).unwrap(), &mesh).is_none();
+                        if same {
+                            if path.exists() {
+                                std::fs::remove_file(&path).unwrap();
+                            }
+                            continue;
+                        }
+                        path
+                    }
+                    None => base_path(iteration),
+                };
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                mesh.save_obj(&path).unwrap();
+                continue;
+            }
+            let expected_path = expected_path(iteration);
+            if !expected_path.exists() {
+                continue;
+            }
+
+            let expected = Mesh::<f64>::load_obj(&expected_path).unwrap();
+            compared += 1;
+            if let Some(diff) = compare(&expected, &mesh) {
+                let _ = writeln!(
+                    failures,
+                    "{}, iteration {iteration}:\n{diff}",
+                    name.to_string_lossy()
+                );
+            }
+        }
+    }
+
+    assert!(update || compared > 0, "no stored results found");
+    assert!(
+        failures.is_empty(),
+        "cantellation results differ from stored snapshots:\n\n{failures}"
+    );
+}
+
+/// Cell of the spatial hash of vertex positions.
+type Cell = [i64; 3];
+
+/// Vertices of the expected mesh which are equal by position share the same canonical index.
+struct PositionIndex {
+    cells: BTreeMap<Cell, Vec<usize>>,
+    positions: Vec<[f64; 3]>,
+}
+
+impl PositionIndex {
+    fn new(mesh: &Mesh<f64>) -> (Self, Vec<usize>) {
+        let mut index = Self {
+            cells: BTreeMap::new(),
+            positions: Vec::new(),
+        };
+        let canonical = mesh
+            .vertices
+            .iter()
+            .map(|v| {
+                let position = [v.x, v.y, v.z];
+                index.find(position).unwrap_or_else(|| {
+                    index.positions.push(position);
+                    let id = index.positions.len() - 1;
+                    index.cells.entry(cell(position)).or_default().push(id);
+                    id
+                })
+            })
+            .collect();
+        (index, canonical)
+    }
+
+    /// Canonical index of the position within the tolerance.
+    fn find(&self, position: [f64; 3]) -> Option<usize> {
+        let [x, y, z] = cell(position);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(ids) = self.cells.get(&[x + dx, y + dy, z + dz]) else {
+                        continue;
+                    };
+                    let found = ids.iter().find(|&&id| {
+                        let other = self.positions[id];
+                        (0..3).all(|i| (other[i] - position[i]).abs() <= TOLERANCE)
+                    });
+                    if let Some(&id) = found {
+                        return Some(id);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+fn cell(position: [f64; 3]) -> Cell {
+    position.map(|value| (value / TOLERANCE).floor() as i64)
+}
+
+/// Face as a cycle of canonical vertex indices starting from the smallest one.
+/// The direction is kept, so flipped faces are different.
+fn face_key(face: impl Iterator<Item = Option<usize>>) -> Option<Vec<usize>> {
+    let mut key: Vec<usize> = face.collect::<Option<_>>()?;
+    let start = (0..key.len()).min_by_key(|&i| key[i]).unwrap_or(0);
+    key.rotate_left(start);
+    Some(key)
+}
+
+/// Readable description of differences between meshes or `None` if they are equal.
+fn compare(expected: &Mesh<f64>, actual: &Mesh<f64>) -> Option<String> {
+    let mut diff = String::new();
+    if expected.vertices.len() != actual.vertices.len() {
+        let _ = writeln!(
+            diff,
+            "  vertices count: expected {}, actual {}",
+            expected.vertices.len(),
+            actual.vertices.len()
+        );
+    }
+    if expected.faces.len() != actual.faces.len() {
+        let _ = writeln!(
+            diff,
+            "  faces count: expected {}, actual {}",
+            expected.faces.len(),
+            actual.faces.len()
+        );
+    }
+
+    let (index, expected_canonical) = PositionIndex::new(expected);
+    let actual_canonical: Vec<Option<usize>> = actual
+        .vertices
+        .iter()
+        .map(|v| index.find([v.x, v.y, v.z]))
+        .collect();
+
+    let unknown: Vec<_> = actual
+        .vertices
+        .iter()
+        .zip(&actual_canonical)
+        .filter(|(_, canonical)| canonical.is_none())
+        .map(|(v, _)| [v.x, v.y, v.z])
+        .collect();
+    if !unknown.is_empty() {
+        let _ = writeln!(diff, "  unexpected vertices: {}", unknown.len());
+        for position in unknown.iter().take(REPORT_LIMIT) {
+            let _ = writeln!(diff, "    {}", format_position(*position));
+        }
+    }
+
+    // multisets of faces, faces with unexpected vertices are reported by positions
+    let mut faces: BTreeMap<Vec<usize>, i64> = BTreeMap::new();
+    for face in &expected.faces {
+        let key = face_key(face.iter().map(|&i| Some(expected_canonical[i]))).unwrap();
+        *faces.entry(key).or_default() += 1;
+    }
+    let mut unexpected_faces = Vec::new();
+    for face in &actual.faces {
+        match face_key(face.iter().map(|&i| actual_canonical[i])) {
+            Some(key) => *faces.entry(key).or_default() -= 1,
+            None => unexpected_faces.push(
+                face.iter()
+                    .map(|&i| {
+                        let v = actual.vertices[i];
+                        format_position([v.x, v.y, v.z])
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        }
+    }
+    let format_face = |key: &Vec<usize>| {
+        key.iter()
+            .map(|&id| format_position(index.positions[id]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let missing: Vec<_> = faces
+        .iter()
+        .filter(|(_, &count)| count > 0)
+        .map(|(key, _)| format_face(key))
+        .collect();
+    unexpected_faces.extend(
+        faces
+            .iter()
+            .filter(|(_, &count)| count < 0)
+            .map(|(key, _)| format_face(key)),
+    );
+
+    for (title, faces) in [("missing", &missing), ("unexpected", &unexpected_faces)] {
+        if !faces.is_empty() {
+            let _ = writeln!(diff, "  {title} faces: {}", faces.len());
+            for face in faces.iter().take(REPORT_LIMIT) {
+                let _ = writeln!(diff, "    [{face}]");
+            }
+        }
+    }
+
+    (!diff.is_empty()).then_some(diff)
+}
+
+fn format_position(position: [f64; 3]) -> String {
+    format!(
+        "({:.4}, {:.4}, {:.4})",
+        position[0], position[1], position[2]
+    )
+}
@@ -0,0 +1,76 @@
+//! Catmull-Clark and Doo-Sabin subdivision.
+
+use cantellation::{
+    mesh::Mesh,
+    primitives::Primitive,
+    stats::MeshStats,
+    subdivide::{catmull_clark, doo_sabin, subdivide, Scheme},
+    tolerance::Tolerances,
+    validate::ValidationReport,
+};
+
+const EPSILON: f64 = 1e-9;
+
+#[test]
+fn catmull_clark_cube() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 2.0 });
+    let result = catmull_clark(&cube);
+    // 8 vertices, 12 edge points and 6 face points
+    assert_eq!(result.vertices.len(), 26);
+    assert_eq!(result.faces.len(), 24);
+    assert!(result.faces.iter().all(|face| face.len() == 4));
+    assert!(ValidationReport::new(&result).is_valid());
+
+    // the corner (1, 1, 1) moves to 5/9 of its position
+    for (before, after) in cube.vertices.iter().zip(&result.vertices) {
+        assert!((*before * (5.0 / 9.0) - *after).length() < EPSILON);
+    }
+    let stats = MeshStats::new(&result, &Tolerances::new(EPSILON));
+    assert_eq!(stats.euler_characteristic, 2);
+    assert!(stats.volume > 0.0);
+}
+
+#[test]
+fn catmull_clark_keeps_boundary_corners() {
+    let quad = Mesh::<f64>::parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+    let result = subdivide(&quad, Scheme::CatmullClark, 2);
+    assert_eq!(result.faces.len(), 16);
+    assert_eq!(result.vertices.len(), 25);
+    assert_eq!(result.vertices[..4], quad.vertices[..]);
+    assert_eq!(
+        MeshStats::new(&result, &Tolerances::new(EPSILON)).boundary_edges,
+        16
+    );
+}
+
+#[test]
+fn doo_sabin_cube_is_expanded() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 2.0 });
+    let result = doo_sabin(&cube);
+    // shrunk faces, quads of edges and triangles of vertices like the cantellated cube
+    assert_eq!(result.vertices.len(), 24);
+    assert_eq!(result.faces.len(), 6 + 12 + 8);
+    assert_eq!(
+        result.faces.iter().filter(|face| face.len() == 3).count(),
+        8
+    );
+    assert!(ValidationReport::new(&result).is_valid());
+    let stats = MeshStats::new(&result, &Tolerances::new(EPSILON));
+    assert_eq!(stats.boundary_edges, 0);
+    assert_eq!(stats.euler_characteristic, 2);
+    assert!(stats.volume > 0.0);
+}
+
+#[test]
+fn doo_sabin_open_grid() {
+    let grid = Mesh::<f64>::generate(&Primitive::Grid {
+        width: 2.0,
+        depth: 2.0,
+        x_segments: 2,
+        y_segments: 2,
+    });
+    let result = doo_sabin(&grid);
+    // 4 faces, 4 interior edges and the single interior vertex
+    assert_eq!(result.faces.len(), 4 + 4 + 1);
+    assert!(ValidationReport::new(&result).is_valid());
+}
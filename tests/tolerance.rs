@@ -0,0 +1,68 @@
+//! Tolerances relative to the size of the mesh.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams, Placement},
+    mesh::Mesh,
+    primitives::Primitive,
+    simplify::merge_coplanar_faces,
+    tolerance::Tolerances,
+};
+
+#[test]
+fn relative_tolerances_do_not_depend_on_size() {
+    let reference = cantellate(
+        &Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 }),
+        &CantellateParams::new(0.2, Tolerances::new(1e-3)),
+    );
+    for size in [1e-5, 1e-2, 1e3, 1e6] {
+        let cube = Mesh::<f64>::generate(&Primitive::Cube { size });
+        let tolerances = Tolerances::new(1e-3).relative_to(&cube);
+        let result = cantellate(&cube, &CantellateParams::new(0.2 * size, tolerances));
+        assert_eq!(result.faces, reference.faces, "size {size}");
+        for (a, b) in result.vertices.iter().zip(&reference.vertices) {
+            assert!((*a / size - *b).length() < 1e-9, "size {size}");
+        }
+    }
+
+    // the absolute merge tolerance is larger than the tiny cube, so corners are merged
+    let tiny = Mesh::<f64>::generate(&Primitive::Cube { size: 1e-5 });
+    let result = cantellate(&tiny, &CantellateParams::new(0.2e-5, Tolerances::new(1e-3)));
+    assert_ne!(result.faces, reference.faces);
+}
+
+#[test]
+fn large_model_topology_does_not_depend_on_relative_tolerances() {
+    // angular checks of rounding and merging are not scaled by the size of the model
+    let icosahedron = Mesh::<f64>::generate(&Primitive::Icosahedron { radius: 500.0 });
+    let torus = Mesh::<f64>::generate(&Primitive::Torus {
+        major_radius: 600.0,
+        minor_radius: 200.0,
+        major_segments: 12,
+        minor_segments: 8,
+    });
+    for (mesh, all_segments) in [(&icosahedron, [1, 3]), (&torus, [1, 1])] {
+        for placement in [
+            Placement::Normal,
+            Placement::Centroid,
+            Placement::InsetNormal,
+        ] {
+            for segments in all_segments {
+                let absolute = Tolerances::new(1e-3);
+                let relative = absolute.relative_to(mesh);
+                assert!(relative.merge > 1.0);
+                let cantellated = |tolerances| {
+                    let params = CantellateParams {
+                        placement,
+                        segments,
+                        ..CantellateParams::new(100.0, tolerances)
+                    };
+                    let result = cantellate(mesh, &params);
+                    merge_coplanar_faces(&result, 0.0, &tolerances)
+                };
+                let (a, b) = (cantellated(absolute), cantellated(relative));
+                assert_eq!(a.faces, b.faces, "{placement:?}, {segments} segments");
+                assert_eq!(a.vertices.len(), b.vertices.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Splitting of output faces into triangles and quads.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    mesh::Mesh,
+    primitives::Primitive,
+    stats::MeshStats,
+    tolerance::Tolerances,
+    triangulate::{split_faces, OutputFaces},
+};
+
+const EPSILON: f64 = 1e-6;
+
+/// Signed area of the face projected to the XY plane.
+fn area_xy(mesh: &Mesh<f64>, face: &[usize]) -> f64 {
+    (0..face.len())
+        .map(|i| {
+            let (a, b) = (
+                mesh.vertices[face[i]],
+                mesh.vertices[face[(i + 1) % face.len()]],
+            );
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+#[test]
+fn non_convex_polygon_is_triangulated() {
+    // L-shaped hexagon in the XY plane, the corner at (1, 1) is reflex, the second vertex is lifted a bit
+    let text = "v 0 0 0\nv 2 0 0.01\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nf 1 2 3 4 5 6\nf 1 6 5\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    let (result, sources) = split_faces(&mesh, OutputFaces::Tri, &Tolerances::new(EPSILON));
+
+    assert_eq!(sources, [0, 0, 0, 0, 1]);
+    assert_eq!(result.vertices, mesh.vertices);
+    assert_eq!(result.faces.len(), 5);
+    assert_eq!(result.faces[4], mesh.faces[1]);
+    assert!(result.faces.iter().all(|face| face.len() == 3));
+    // triangles keep the orientation and cover the polygon without overlaps
+    assert!(result.faces[..4]
+        .iter()
+        .all(|face| area_xy(&result, face) > 0.0));
+    let total: f64 = result.faces[..4]
+        .iter()
+        .map(|face| area_xy(&result, face))
+        .sum();
+    assert!((total - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn convex_polygon_is_split_into_quads() {
+    let octagon: String = (0..8)
+        .map(|i| {
+            let angle = i as f64 * std::f64::consts::TAU / 8.0;
+            format!("v {} {} 0\n", angle.cos(), angle.sin())
+        })
+        .collect::<String>()
+        + "f 1 2 3 4 5 6 7 8\n";
+    let mesh = Mesh::<f64>::parse_obj(&octagon).unwrap();
+    let (result, sources) = split_faces(&mesh, OutputFaces::Quad, &Tolerances::new(EPSILON));
+    assert_eq!(result.faces.len(), 3);
+    assert!(result.faces.iter().all(|face| face.len() == 4));
+    assert_eq!(sources, [0, 0, 0]);
+
+    let (same, _) = split_faces(&mesh, OutputFaces::Ngon, &Tolerances::new(EPSILON));
+    assert_eq!(same.faces, mesh.faces);
+}
+
+#[test]
+fn cantellated_mesh_stays_closed() {
+    let params = CantellateParams::new(0.3, Tolerances::new(EPSILON));
+    let mesh = (0..3).fold(
+        Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 }),
+        |mesh, _| cantellate(&mesh, &params),
+    );
+    let before = MeshStats::new(&mesh, &Tolerances::new(EPSILON));
+    for output in [OutputFaces::Tri, OutputFaces::Quad] {
+        let (result, _) = split_faces(&mesh, output, &Tolerances::new(EPSILON));
+        let after = MeshStats::new(&result, &Tolerances::new(EPSILON));
+        assert_eq!(after.boundary_edges, 0);
+        assert_eq!(after.euler_characteristic, before.euler_characteristic);
+        assert!((after.volume - before.volume).abs() < 1e-3 * before.volume);
+    }
+}
+
+#[test]
+fn split_faces_keep_texture_coordinates_and_groups() {
+    // UVs of the hexagon corners are their XY positions, the quad is in its own group
+    let text = "v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nv 3 0 0\nv 3 1 0\n\
+        vt 0 0\nvt 2 0\nvt 2 1\nvt 1 1\nvt 1 2\nvt 0 2\n\
+        g hexagon\nf 1/1 2/2 3/3 4/4 5/5 6/6\ng quad\nf 2 7 8 3\n";
+    let (mesh, groups) = Mesh::<f64>::parse_obj_groups(text).unwrap();
+    assert_eq!(mesh.face_texcoords(0), Some(&[0, 1, 2, 3, 4, 5][..]));
+    assert_eq!(mesh.face_texcoords(1), None);
+
+    for output in [OutputFaces::Tri, OutputFaces::Quad] {
+        let (result, sources) = split_faces(&mesh, output, &Tolerances::new(EPSILON));
+        assert_eq!(result.texcoords, mesh.texcoords);
+        assert_eq!(result.face_texcoords.len(), result.faces.len());
+        for (face_index, face) in result.faces.iter().enumerate() {
+            match sources[face_index] {
+                0 => {
+                    // each corner takes the UV of the same corner of the source face
+                    let texcoords = result.face_texcoords(face_index).unwrap();
+                    for (&vertex, &texcoord) in face.iter().zip(texcoords) {
+                        let (position, uv) = (result.vertices[vertex], result.texcoords[texcoord]);
+                        assert_eq!([position.x, position.y], uv);
+                    }
+                }
+                _ => assert_eq!(result.face_texcoords(face_index), None),
+            }
+        }
+
+        // groups and UVs are saved and loaded back
+        let path = std::env::temp_dir().join(format!(
+            "cantellation_{}_split_{output:?}.obj",
+            std::process::id()
+        ));
+        let groups: Vec<String> = sources
+            .iter()
+            .map(|&source| groups[source].clone())
+            .collect();
+        result.save_obj_groups(&path, &groups).unwrap();
+        let (loaded, loaded_groups) = Mesh::<f64>::load_obj_groups(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded_groups, groups);
+        assert_eq!(loaded.faces, result.faces);
+        assert_eq!(loaded.face_texcoords, result.face_texcoords);
+        assert_eq!(loaded.texcoords, result.texcoords);
+    }
+}
@@ -0,0 +1,95 @@
+//! Recovering the original mesh from its cantellation.
+
+use cantellation::{
+    cantellate::{cantellate, CantellateParams},
+    mesh::Mesh,
+    primitives::Primitive,
+    tolerance::Tolerances,
+    uncantellate::uncantellate,
+};
+use std::path::Path;
+
+const EPSILON: f64 = 1e-3;
+
+/// Cantellate the mesh with faces pushed along normals, uncantellate it back and compare with the source.
+fn round_trip(mesh: &Mesh<f64>, factor: f64) {
+    let tolerances = Tolerances::new(EPSILON);
+    let params = CantellateParams::new(factor, tolerances);
+    let uncantellated = uncantellate(&cantellate(mesh, &params), &tolerances).unwrap();
+    assert!((uncantellated.factor - factor).abs() < EPSILON);
+
+    let result = uncantellated.mesh;
+    assert_eq!(result.vertices.len(), mesh.vertices.len());
+    assert_eq!(result.faces.len(), mesh.faces.len());
+    // each vertex is recovered, faces are compared by their vertex positions
+    let source_index = |index: usize| {
+        let vertex = result.vertices[index];
+        let found = mesh
+            .vertices
+            .iter()
+            .position(|&source| (source - vertex).length() < EPSILON);
+        found.unwrap_or_else(|| panic!("vertex {vertex:?} is not in the source mesh"))
+    };
+    let mut source_faces: Vec<Vec<usize>> =
+        mesh.faces.iter().map(|face| normalized(face)).collect();
+    let mut result_faces: Vec<Vec<usize>> = result
+        .faces
+        .iter()
+        .map(|face| {
+            normalized(
+                &face
+                    .iter()
+                    .map(|&index| source_index(index))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    source_faces.sort();
+    result_faces.sort();
+    assert_eq!(result_faces, source_faces);
+}
+
+/// Rotate the face to start from the minimal index, the winding is kept.
+fn normalized(face: &[usize]) -> Vec<usize> {
+    let start = (0..face.len()).min_by_key(|&i| face[i]).unwrap_or(0);
+    face[start..]
+        .iter()
+        .chain(&face[..start])
+        .copied()
+        .collect()
+}
+
+#[test]
+fn cube_round_trip() {
+    round_trip(&Mesh::generate(&Primitive::Cube { size: 1.0 }), 0.1);
+}
+
+#[test]
+fn icosahedron_round_trip() {
+    round_trip(
+        &Mesh::generate(&Primitive::Icosahedron { radius: 1.0 }),
+        0.1,
+    );
+}
+
+#[test]
+fn torus_round_trip() {
+    let torus = Mesh::generate(&Primitive::Torus {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+        major_segments: 24,
+        minor_segments: 12,
+    });
+    // edge strips between near-coplanar faces are thinner than the normal tolerance
+    for factor in [0.02, 0.1] {
+        round_trip(&torus, factor);
+    }
+}
+
+#[test]
+fn torus_asset_round_trip() {
+    let torus =
+        Mesh::<f64>::load_obj(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/torus.obj"))
+            .unwrap();
+    round_trip(&torus, 0.05);
+}
@@ -0,0 +1,74 @@
+//! Validation and repair of the mesh topology.
+
+use cantellation::{
+    mesh::Mesh,
+    primitives::Primitive,
+    validate::{repair, ValidationReport},
+    vec3::Vec3,
+};
+use std::path::Path;
+
+fn asset(name: &str) -> Mesh<f64> {
+    Mesh::load_obj(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(name),
+    )
+    .unwrap()
+}
+
+#[test]
+fn repair_keeps_valid_mesh() {
+    let cube = Mesh::<f64>::generate(&Primitive::Cube { size: 1.0 });
+    assert!(ValidationReport::new(&cube).is_valid());
+    let repaired = repair(&cube);
+    assert_eq!(repaired.vertices.len(), cube.vertices.len());
+    assert_eq!(repaired.faces.len(), cube.faces.len());
+
+    // vertices are reordered by their first use, faces are kept
+    let planes = asset("two_different_directed_planes.obj");
+    assert_eq!(repair(&planes).faces, planes.faces);
+}
+
+#[test]
+fn repair_removes_duplicate_faces() {
+    let mesh = asset("face_duplications.obj");
+    let report = ValidationReport::new(&mesh);
+    assert_eq!(report.duplicate_faces, [1, 3, 5, 7, 9, 11]);
+    let repaired = repair(&mesh);
+    assert_eq!(repaired.faces.len(), 6);
+    assert!(ValidationReport::new(&repaired).is_valid());
+}
+
+#[test]
+fn faces_with_other_winding_are_not_duplicates() {
+    // the same face rotated, the opposite face and another polygon on the same vertices
+    let text = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\nf 3 4 1 2\nf 4 3 2 1\nf 1 3 2 4\n";
+    let mesh = Mesh::<f64>::parse_obj(text).unwrap();
+    assert_eq!(ValidationReport::new(&mesh).duplicate_faces, [1]);
+    let repaired = repair(&mesh);
+    assert_eq!(repaired.faces.len(), 3);
+    assert_eq!(repaired.faces[1].as_slice(), &[3, 2, 1, 0]);
+}
+
+#[test]
+fn repair_removes_invalid_and_degenerate_faces() {
+    let mut mesh = Mesh::<f64>::generate(&Primitive::Tetrahedron { radius: 1.0 });
+    let valid_faces = mesh.faces.clone();
+    mesh.vertices.push(Vec3::zero());
+    mesh.faces.push([0, 1, 99].into_iter().collect());
+    mesh.faces.push([0, 1].into_iter().collect());
+    mesh.faces.push([0, 1, 0].into_iter().collect());
+    // the repeated consecutive vertex is removed, the face is kept
+    mesh.faces.push([2, 2, 1, 0].into_iter().collect());
+
+    let report = ValidationReport::new(&mesh);
+    assert_eq!(report.invalid_indices, [4]);
+    assert_eq!(report.degenerate_faces, [5, 6, 7]);
+    assert_eq!(report.unused_vertices, [4]);
+
+    let repaired = repair(&mesh);
+    assert_eq!(repaired.vertices.len(), 4);
+    assert_eq!(repaired.faces[..4], valid_faces[..]);
+    assert_eq!(repaired.faces[4].as_slice(), &[2, 1, 0]);
+}